@@ -0,0 +1,2 @@
+-- Quantities are expressed in a unit, existing rows are counted pieces
+ALTER TABLE item ADD COLUMN unit VARCHAR(8) NOT NULL DEFAULT 'pcs' CHECK (unit IN ('g', 'kg', 'ml', 'l', 'pcs'));
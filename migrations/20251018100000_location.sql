@@ -0,0 +1,8 @@
+-- Locations form a tree, e.g. house > kitchen > fridge > door shelf
+CREATE TABLE location (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR(255) NOT NULL,
+    parent_id INTEGER REFERENCES location (id) ON DELETE CASCADE
+);
+
+ALTER TABLE item ADD COLUMN location_id INTEGER REFERENCES location (id) ON DELETE SET NULL;
@@ -0,0 +1,4 @@
+-- Best-before date, unset for items that do not go off
+ALTER TABLE item ADD COLUMN expires_on DATE;
+
+CREATE INDEX item_expires_on ON item (expires_on) WHERE expires_on IS NOT NULL;
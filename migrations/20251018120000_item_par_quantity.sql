@@ -0,0 +1,2 @@
+-- Minimum quantity to keep in stock, expressed in the unit of the item
+ALTER TABLE item ADD COLUMN par_quantity DOUBLE PRECISION CHECK (par_quantity >= 0);
@@ -0,0 +1,12 @@
+-- Audit log of every change made to an item, rows outlive the item they describe
+CREATE TABLE item_event (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    kind VARCHAR(16) NOT NULL CHECK (kind IN ('created', 'updated', 'deleted')),
+    old_value TEXT,
+    new_value TEXT,
+    actor VARCHAR(255),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX item_event_item_id ON item_event (item_id);
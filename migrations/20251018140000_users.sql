@@ -0,0 +1,31 @@
+-- Households own the pantry data, users sign in to exactly one household
+CREATE TABLE household (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR(255) NOT NULL UNIQUE
+);
+
+CREATE TABLE user (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username VARCHAR(255) NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    household_id INTEGER NOT NULL REFERENCES household (id) ON DELETE CASCADE
+);
+
+CREATE TABLE session (
+    token VARCHAR(64) PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES user (id) ON DELETE CASCADE,
+    expires_at TIMESTAMP NOT NULL
+);
+
+-- Everything recorded before accounts existed belongs to the first household
+INSERT INTO household ( id, name ) VALUES (1, 'Home');
+
+ALTER TABLE item ADD COLUMN household_id INTEGER REFERENCES household (id) ON DELETE CASCADE;
+UPDATE item SET household_id = 1;
+CREATE INDEX item_household_id_state ON item (household_id, state);
+
+ALTER TABLE location ADD COLUMN household_id INTEGER REFERENCES household (id) ON DELETE CASCADE;
+UPDATE location SET household_id = 1;
+
+ALTER TABLE item_event ADD COLUMN household_id INTEGER REFERENCES household (id) ON DELETE CASCADE;
+UPDATE item_event SET household_id = 1;
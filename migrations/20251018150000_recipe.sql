@@ -0,0 +1,18 @@
+-- Recipes list the pantry items they need. Ingredients keep the name of their item, a
+-- recipe outlives the pantry items it was written with.
+CREATE TABLE recipe (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR(255) NOT NULL,
+    household_id INTEGER NOT NULL REFERENCES household (id) ON DELETE CASCADE
+);
+
+CREATE TABLE ingredient (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    recipe_id INTEGER NOT NULL REFERENCES recipe (id) ON DELETE CASCADE,
+    item_id INTEGER NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    quantity REAL NOT NULL,
+    unit VARCHAR(8) NOT NULL DEFAULT 'pcs' CHECK (unit IN ('g', 'kg', 'ml', 'l', 'pcs'))
+);
+
+CREATE INDEX ingredient_recipe_id ON ingredient (recipe_id);
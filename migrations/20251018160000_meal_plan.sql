@@ -0,0 +1,7 @@
+-- Recipes picked for days of the week, weekday 0 is Monday
+CREATE TABLE meal_plan (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    weekday INTEGER NOT NULL CHECK (weekday BETWEEN 0 AND 6),
+    recipe_id INTEGER NOT NULL REFERENCES recipe (id) ON DELETE CASCADE,
+    household_id INTEGER NOT NULL REFERENCES household (id) ON DELETE CASCADE
+);
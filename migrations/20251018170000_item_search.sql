@@ -0,0 +1,23 @@
+-- Trigram index over item names, matches substrings of three or more characters.
+-- External content table, the triggers keep it in sync with item.
+CREATE VIRTUAL TABLE item_search USING fts5 (
+    name,
+    content = 'item',
+    content_rowid = 'id',
+    tokenize = 'trigram'
+);
+
+INSERT INTO item_search (item_search) VALUES ('rebuild');
+
+CREATE TRIGGER item_search_insert AFTER INSERT ON item BEGIN
+    INSERT INTO item_search (rowid, name) VALUES (new.id, new.name);
+END;
+
+CREATE TRIGGER item_search_delete AFTER DELETE ON item BEGIN
+    INSERT INTO item_search (item_search, rowid, name) VALUES ('delete', old.id, old.name);
+END;
+
+CREATE TRIGGER item_search_update AFTER UPDATE OF name ON item BEGIN
+    INSERT INTO item_search (item_search, rowid, name) VALUES ('delete', old.id, old.name);
+    INSERT INTO item_search (rowid, name) VALUES (new.id, new.name);
+END;
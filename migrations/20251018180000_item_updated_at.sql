@@ -0,0 +1,15 @@
+-- When an item last changed, for sorting by recently updated
+ALTER TABLE item ADD COLUMN updated_at TIMESTAMP;
+UPDATE item SET updated_at = COALESCE(
+    (SELECT MAX(created_at) FROM item_event WHERE item_event.item_id = item.id),
+    CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER item_touch_insert AFTER INSERT ON item BEGIN
+    UPDATE item SET updated_at = CURRENT_TIMESTAMP WHERE id = new.id;
+END;
+
+CREATE TRIGGER item_touch_update
+AFTER UPDATE OF name, quantity, unit, state, location_id, expires_on, par_quantity ON item BEGIN
+    UPDATE item SET updated_at = CURRENT_TIMESTAMP WHERE id = new.id;
+END;
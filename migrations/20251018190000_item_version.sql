@@ -0,0 +1,2 @@
+-- Incremented by every write, updates carrying an older version are rejected
+ALTER TABLE item ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,59 @@
+-- States are configured now, any id of 0 or more may be stored. SQLite can't drop a CHECK
+-- constraint, so the table is rebuilt.
+CREATE TABLE item_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR(255) NOT NULL,
+    quantity DOUBLE PRECISION NOT NULL,
+    state INTEGER NOT NULL CHECK (state >= 0),
+    unit VARCHAR(8) NOT NULL DEFAULT 'pcs' CHECK (unit IN ('g', 'kg', 'ml', 'l', 'pcs')),
+    location_id INTEGER REFERENCES location (id) ON DELETE SET NULL,
+    expires_on DATE,
+    par_quantity DOUBLE PRECISION CHECK (par_quantity >= 0),
+    household_id INTEGER REFERENCES household (id) ON DELETE CASCADE,
+    updated_at TIMESTAMP,
+    version INTEGER NOT NULL DEFAULT 1
+);
+
+-- 2 was an old spelling of shopping
+INSERT INTO item_new (
+    id, name, quantity, state, unit, location_id, expires_on, par_quantity, household_id,
+    updated_at, version
+)
+SELECT id, name, quantity, CASE state WHEN 2 THEN 1 ELSE state END, unit, location_id,
+    expires_on, par_quantity, household_id, updated_at, version
+FROM item;
+
+-- Ids of deleted items stay unused, their history still refers to them
+DELETE FROM sqlite_sequence WHERE name = 'item_new';
+INSERT INTO sqlite_sequence (name, seq)
+SELECT 'item_new', seq FROM sqlite_sequence WHERE name = 'item';
+
+-- Takes the old indexes and triggers along
+DROP TABLE item;
+
+ALTER TABLE item_new RENAME TO item;
+
+CREATE INDEX item_expires_on ON item (expires_on) WHERE expires_on IS NOT NULL;
+CREATE INDEX item_household_id_state ON item (household_id, state);
+
+CREATE TRIGGER item_search_insert AFTER INSERT ON item BEGIN
+    INSERT INTO item_search (rowid, name) VALUES (new.id, new.name);
+END;
+
+CREATE TRIGGER item_search_delete AFTER DELETE ON item BEGIN
+    INSERT INTO item_search (item_search, rowid, name) VALUES ('delete', old.id, old.name);
+END;
+
+CREATE TRIGGER item_search_update AFTER UPDATE OF name ON item BEGIN
+    INSERT INTO item_search (item_search, rowid, name) VALUES ('delete', old.id, old.name);
+    INSERT INTO item_search (rowid, name) VALUES (new.id, new.name);
+END;
+
+CREATE TRIGGER item_touch_insert AFTER INSERT ON item BEGIN
+    UPDATE item SET updated_at = CURRENT_TIMESTAMP WHERE id = new.id;
+END;
+
+CREATE TRIGGER item_touch_update
+AFTER UPDATE OF name, quantity, unit, state, location_id, expires_on, par_quantity ON item BEGIN
+    UPDATE item SET updated_at = CURRENT_TIMESTAMP WHERE id = new.id;
+END;
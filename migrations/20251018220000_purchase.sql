@@ -0,0 +1,15 @@
+-- Prices paid for items moved from the shopping list to the stock. The purchase keeps the
+-- name of the item, the reports still show it after the item is deleted.
+CREATE TABLE purchase (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    item_id INTEGER NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    quantity DOUBLE PRECISION NOT NULL,
+    unit VARCHAR(8) NOT NULL DEFAULT 'pcs' CHECK (unit IN ('g', 'kg', 'ml', 'l', 'pcs')),
+    price_cents INTEGER NOT NULL CHECK (price_cents >= 0),
+    shop VARCHAR(255),
+    purchased_on DATE NOT NULL,
+    household_id INTEGER NOT NULL REFERENCES household (id) ON DELETE CASCADE
+);
+
+CREATE INDEX purchase_household_id_purchased_on ON purchase (household_id, purchased_on);
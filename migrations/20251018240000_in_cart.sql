@@ -0,0 +1,2 @@
+-- Shopping list items ticked off during a trip, they move to the stock at the check out
+ALTER TABLE item ADD COLUMN in_cart BOOLEAN NOT NULL DEFAULT FALSE;
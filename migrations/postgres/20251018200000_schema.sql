@@ -0,0 +1,97 @@
+-- Schema of the sqlite migrations up to item_version, in one step. Integers are BIGINT so
+-- they read back as i64 like they do from sqlite. Timestamps are UTC like sqlite's
+-- CURRENT_TIMESTAMP.
+CREATE TABLE household (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name VARCHAR(255) NOT NULL UNIQUE
+);
+
+CREATE TABLE "user" (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    username VARCHAR(255) NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    household_id BIGINT NOT NULL REFERENCES household (id) ON DELETE CASCADE
+);
+
+CREATE TABLE session (
+    token VARCHAR(64) PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+    expires_at TIMESTAMP NOT NULL
+);
+
+-- Locations form a tree, e.g. house > kitchen > fridge > door shelf
+CREATE TABLE location (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    parent_id BIGINT REFERENCES location (id) ON DELETE CASCADE,
+    household_id BIGINT REFERENCES household (id) ON DELETE CASCADE
+);
+
+CREATE TABLE item (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    quantity DOUBLE PRECISION NOT NULL,
+    state BIGINT NOT NULL CHECK (state IN (0, 1, 2)),
+    unit VARCHAR(8) NOT NULL DEFAULT 'pcs' CHECK (unit IN ('g', 'kg', 'ml', 'l', 'pcs')),
+    location_id BIGINT REFERENCES location (id) ON DELETE SET NULL,
+    expires_on DATE,
+    par_quantity DOUBLE PRECISION CHECK (par_quantity >= 0),
+    household_id BIGINT REFERENCES household (id) ON DELETE CASCADE,
+    updated_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    version BIGINT NOT NULL DEFAULT 1
+);
+
+CREATE INDEX item_expires_on ON item (expires_on) WHERE expires_on IS NOT NULL;
+CREATE INDEX item_household_id_state ON item (household_id, state);
+
+CREATE FUNCTION item_touch() RETURNS trigger AS $$
+BEGIN
+    NEW.updated_at := now() AT TIME ZONE 'UTC';
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER item_touch_update
+BEFORE UPDATE OF name, quantity, unit, state, location_id, expires_on, par_quantity ON item
+FOR EACH ROW EXECUTE FUNCTION item_touch();
+
+-- Audit log of every change made to an item, rows outlive the item they describe
+CREATE TABLE item_event (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    item_id BIGINT NOT NULL,
+    kind VARCHAR(16) NOT NULL CHECK (kind IN ('created', 'updated', 'deleted')),
+    old_value TEXT,
+    new_value TEXT,
+    actor VARCHAR(255),
+    created_at TIMESTAMP NOT NULL DEFAULT (now() AT TIME ZONE 'UTC'),
+    household_id BIGINT REFERENCES household (id) ON DELETE CASCADE
+);
+
+CREATE INDEX item_event_item_id ON item_event (item_id);
+
+-- Recipes list the pantry items they need. Ingredients keep the name of their item, a
+-- recipe outlives the pantry items it was written with.
+CREATE TABLE recipe (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    household_id BIGINT NOT NULL REFERENCES household (id) ON DELETE CASCADE
+);
+
+CREATE TABLE ingredient (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    recipe_id BIGINT NOT NULL REFERENCES recipe (id) ON DELETE CASCADE,
+    item_id BIGINT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    quantity DOUBLE PRECISION NOT NULL,
+    unit VARCHAR(8) NOT NULL DEFAULT 'pcs' CHECK (unit IN ('g', 'kg', 'ml', 'l', 'pcs'))
+);
+
+CREATE INDEX ingredient_recipe_id ON ingredient (recipe_id);
+
+-- Recipes picked for days of the week, weekday 0 is Monday
+CREATE TABLE meal_plan (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    weekday BIGINT NOT NULL CHECK (weekday BETWEEN 0 AND 6),
+    recipe_id BIGINT NOT NULL REFERENCES recipe (id) ON DELETE CASCADE,
+    household_id BIGINT NOT NULL REFERENCES household (id) ON DELETE CASCADE
+);
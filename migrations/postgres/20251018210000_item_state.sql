@@ -0,0 +1,7 @@
+-- States are configured now, any id of 0 or more may be stored
+ALTER TABLE item DROP CONSTRAINT item_state_check;
+
+-- 2 was an old spelling of shopping
+UPDATE item SET state = 1 WHERE state = 2;
+
+ALTER TABLE item ADD CONSTRAINT item_state_check CHECK (state >= 0);
@@ -0,0 +1,24 @@
+-- Shops list their aisles in the order they are walked, an item sits in one aisle per shop
+CREATE TABLE shop (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    household_id BIGINT NOT NULL REFERENCES household (id) ON DELETE CASCADE
+);
+
+CREATE TABLE aisle (
+    id BIGINT GENERATED ALWAYS AS IDENTITY PRIMARY KEY,
+    shop_id BIGINT NOT NULL REFERENCES shop (id) ON DELETE CASCADE,
+    name VARCHAR(255) NOT NULL,
+    position BIGINT NOT NULL
+);
+
+CREATE INDEX aisle_shop_id_position ON aisle (shop_id, position);
+
+CREATE TABLE item_aisle (
+    item_id BIGINT NOT NULL REFERENCES item (id) ON DELETE CASCADE,
+    shop_id BIGINT NOT NULL REFERENCES shop (id) ON DELETE CASCADE,
+    aisle_id BIGINT NOT NULL REFERENCES aisle (id) ON DELETE CASCADE,
+    PRIMARY KEY (item_id, shop_id)
+);
+
+CREATE INDEX item_aisle_shop_id ON item_aisle (shop_id);
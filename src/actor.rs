@@ -0,0 +1,46 @@
+use axum::{extract::FromRequestParts, http::StatusCode, http::request::Parts};
+
+use crate::user::User;
+
+/// Signed-in user a request runs on behalf of, store calls are scoped to their household.
+#[derive(Clone, Debug)]
+pub struct Actor {
+    name: String,
+    household_id: i64,
+}
+
+impl Actor {
+    pub fn new(name: String, household_id: i64) -> Self {
+        Self { name, household_id }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn household_id(&self) -> i64 {
+        self.household_id
+    }
+}
+
+impl From<User> for Actor {
+    fn from(user: User) -> Self {
+        Self::new(user.username, user.household_id)
+    }
+}
+
+impl<S> FromRequestParts<S> for Actor
+where
+    S: Send + Sync,
+{
+    type Rejection = StatusCode;
+
+    // The auth middleware stores the actor in the request extensions
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Actor>()
+            .cloned()
+            .ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{
+    actor::Actor, error_fragment, item::State as ItemState, live_updates::LiveUpdates,
+    store::ItemStore,
+};
+use axum::{
+    Form, debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct AdjustQuantityForm {
+    amount: f64,
+}
+
+impl AdjustQuantityForm {
+    fn is_valid(&self) -> bool {
+        self.amount.is_finite() && self.amount > 0.0
+    }
+}
+
+#[debug_handler(state = crate::AppState)]
+pub async fn consume_item(
+    State(pool): State<ItemStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Form(form): Form<AdjustQuantityForm>,
+) -> impl IntoResponse {
+    if !form.is_valid() {
+        return (StatusCode::BAD_REQUEST, "Amount must be a positive number").into_response();
+    }
+    match pool.consume(id, form.amount, &actor).await {
+        Ok(_) => {
+            // Stock changes can top up the shopping list as well
+            live.publish(&actor, ItemState::all());
+            StatusCode::OK.into_response()
+        }
+        Err(err) => {
+            tracing::error!(err = %err, id, amount = form.amount, "failed to consume item");
+            error_fragment::store_error(&err, "Failed to consume item")
+        }
+    }
+}
+
+#[debug_handler(state = crate::AppState)]
+pub async fn restock_item(
+    State(pool): State<ItemStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Form(form): Form<AdjustQuantityForm>,
+) -> impl IntoResponse {
+    if !form.is_valid() {
+        return (StatusCode::BAD_REQUEST, "Amount must be a positive number").into_response();
+    }
+    match pool.restock(id, form.amount, &actor).await {
+        Ok(_) => {
+            // Stock changes can top up the shopping list as well
+            live.publish(&actor, ItemState::all());
+            StatusCode::OK.into_response()
+        }
+        Err(err) => {
+            tracing::error!(err = %err, id, amount = form.amount, "failed to restock item");
+            error_fragment::store_error(&err, "Failed to restock item")
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use crate::actor::Actor;
+use crate::item::{Item, Unit};
+use crate::item_validation::{self, ItemFormErrors};
+use crate::live_updates::LiveUpdates;
+use crate::store::{ItemStore, StoreError};
+use axum::{
+    Json, Router,
+    extract::{
+        FromRequest, FromRequestParts, State,
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+    },
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// JSON flavour of the item endpoints, mounted under `/api/v1`.
+pub fn router() -> Router<crate::AppState> {
+    Router::new()
+        .route("/items", get(list_items).post(create_item))
+        .route(
+            "/items/{id}",
+            get(get_item).put(update_item).delete(delete_item),
+        )
+}
+
+#[derive(Serialize)]
+pub struct ItemResource {
+    id: i64,
+    name: String,
+    quantity: f64,
+    unit: Unit,
+    state: crate::item::State,
+    location_id: Option<i64>,
+    expires_on: Option<NaiveDate>,
+    par_quantity: Option<f64>,
+    version: i64,
+}
+
+impl From<Item> for ItemResource {
+    fn from(item: Item) -> Self {
+        Self {
+            id: item.id,
+            name: item.name,
+            quantity: item.quantity,
+            unit: item.unit,
+            state: item.state,
+            location_id: item.location_id,
+            expires_on: item.expires_on,
+            par_quantity: item.par_quantity,
+            version: item.version,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ItemPayload {
+    name: String,
+    quantity: f64,
+    #[serde(default = "default_unit")]
+    unit: Unit,
+    state: crate::item::State,
+    #[serde(default)]
+    location_id: Option<i64>,
+    #[serde(default)]
+    expires_on: Option<NaiveDate>,
+    #[serde(default)]
+    par_quantity: Option<f64>,
+    /// Version the update is based on, without it the update overwrites whatever is stored
+    #[serde(default)]
+    version: Option<i64>,
+}
+
+fn default_unit() -> Unit {
+    Unit::Piece
+}
+
+impl ItemPayload {
+    fn into_item(self, id: i64) -> Item {
+        Item::new(id, self.name, self.quantity, self.unit, self.state)
+            .with_location(self.location_id)
+            .with_expiry(self.expires_on)
+            .with_par(self.par_quantity)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListParameters {
+    state: Option<crate::item::State>,
+    location: Option<i64>,
+    /// Only items expiring within this many days
+    expiring_within: Option<u64>,
+}
+
+pub async fn list_items(
+    State(pool): State<ItemStore>,
+    actor: Actor,
+    ApiQuery(query): ApiQuery<ListParameters>,
+) -> Result<Json<Vec<ItemResource>>, ApiError> {
+    let states = match query.state {
+        Some(state) => vec![state],
+        None => crate::item::State::all().to_vec(),
+    };
+
+    let mut items = Vec::new();
+    for state in states {
+        let mut found = match query.location {
+            Some(location) => {
+                pool.read_many_from_state_in_location(state, location, &actor)
+                    .await?
+            }
+            None => pool.read_many_from_state(state, &actor).await?,
+        };
+        items.append(&mut found);
+    }
+
+    if let Some(days) = query.expiring_within {
+        let until = chrono::Local::now()
+            .date_naive()
+            .checked_add_days(chrono::Days::new(days))
+            .ok_or_else(|| ApiError::invalid_query(format!("{days} days is past the calendar")))?;
+        items.retain(|item| {
+            item.expires_on
+                .is_some_and(|expires_on| expires_on <= until)
+        });
+    }
+
+    Ok(Json(items.into_iter().map(ItemResource::from).collect()))
+}
+
+pub async fn get_item(
+    State(pool): State<ItemStore>,
+    actor: Actor,
+    ApiPath(id): ApiPath<i64>,
+) -> Result<Json<ItemResource>, ApiError> {
+    let item = pool.read(id, &actor).await?;
+    Ok(Json(item.into()))
+}
+
+pub async fn create_item(
+    State(pool): State<ItemStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    ApiJson(payload): ApiJson<ItemPayload>,
+) -> Result<Response, ApiError> {
+    let state = payload.state;
+    let record = item_validation::validate(payload.into_item(0), None)?;
+    let id = pool.create(record, &actor).await?;
+    live.publish(&actor, &[state]);
+    let item = pool.read(id, &actor).await?;
+    Ok((
+        StatusCode::CREATED,
+        [(header::LOCATION, format!("/api/v1/items/{id}"))],
+        Json(ItemResource::from(item)),
+    )
+        .into_response())
+}
+
+pub async fn update_item(
+    State(pool): State<ItemStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    ApiPath(id): ApiPath<i64>,
+    ApiJson(payload): ApiJson<ItemPayload>,
+) -> Result<Json<ItemResource>, ApiError> {
+    // Without a version in the payload the update applies to whatever is saved
+    let current = pool.read(id, &actor).await?;
+    let version = payload.version.unwrap_or(current.version);
+    let record = item_validation::validate(
+        payload.into_item(id).with_version(version),
+        Some(current.state),
+    )?;
+    pool.update(record, &actor).await?;
+    live.publish(&actor, crate::item::State::all());
+    let item = pool.read(id, &actor).await?;
+    Ok(Json(item.into()))
+}
+
+pub async fn delete_item(
+    State(pool): State<ItemStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    ApiPath(id): ApiPath<i64>,
+) -> Result<StatusCode, ApiError> {
+    pool.delete(id, &actor).await?;
+    live.publish(&actor, crate::item::State::all());
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(FromRequest)]
+#[from_request(via(Json), rejection(ApiError))]
+pub struct ApiJson<T>(T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ApiError))]
+pub struct ApiPath<T>(T);
+
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub struct ApiQuery<T>(T);
+
+#[derive(Serialize)]
+struct ErrorBody {
+    error: ErrorDetail,
+}
+
+#[derive(Serialize)]
+struct ErrorDetail {
+    code: &'static str,
+    message: String,
+}
+
+/// Error returned by every API endpoint as `{"error": {"code": ..., "message": ...}}`.
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+impl ApiError {
+    pub fn unauthorized() -> Self {
+        Self {
+            status: StatusCode::UNAUTHORIZED,
+            code: "unauthorized",
+            message: "Sign in to use the API".to_string(),
+        }
+    }
+
+    fn invalid_query(message: String) -> Self {
+        Self {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            code: "invalid_query",
+            message,
+        }
+    }
+}
+
+impl From<StoreError> for ApiError {
+    fn from(err: StoreError) -> Self {
+        match err {
+            StoreError::NotFound => Self {
+                status: StatusCode::NOT_FOUND,
+                code: "not_found",
+                message: "Item not found".to_string(),
+            },
+            StoreError::Conflict => Self {
+                status: StatusCode::CONFLICT,
+                code: "conflict",
+                message: "The item was changed since the given version".to_string(),
+            },
+            StoreError::Validation(message) => Self {
+                status: StatusCode::UNPROCESSABLE_ENTITY,
+                code: "invalid_item",
+                message,
+            },
+            StoreError::Unavailable => Self {
+                status: StatusCode::SERVICE_UNAVAILABLE,
+                code: "unavailable",
+                message: "The item store can't be reached, try again later".to_string(),
+            },
+            err => {
+                tracing::error!(err = %err, "api request failed in the store");
+                Self {
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                    code: "store_error",
+                    message: "The item store failed to process the request".to_string(),
+                }
+            }
+        }
+    }
+}
+
+impl From<ItemFormErrors> for ApiError {
+    fn from(errors: ItemFormErrors) -> Self {
+        Self {
+            status: StatusCode::UNPROCESSABLE_ENTITY,
+            code: "invalid_item",
+            message: errors.summary(),
+        }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self {
+            status: rejection.status(),
+            code: "invalid_body",
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        Self {
+            status: rejection.status(),
+            code: "invalid_path",
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self {
+            status: rejection.status(),
+            code: "invalid_query",
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: ErrorDetail {
+                code: self.code,
+                message: self.message,
+            },
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
@@ -0,0 +1,97 @@
+use crate::actor::Actor;
+use crate::api::ApiError;
+use crate::store::UserStore;
+use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier, password_hash::SaltString};
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar, SameSite};
+use rand_core::{OsRng, RngCore};
+use std::sync::LazyLock;
+
+pub const SESSION_COOKIE: &str = "pantry_session";
+pub const SESSION_DAYS: i64 = 30;
+
+#[derive(Clone, Copy)]
+pub struct SessionSettings {
+    /// Only send the session cookie over https, required when served through the tunnel
+    pub secure_cookies: bool,
+}
+
+impl SessionSettings {
+    pub fn session_cookie(&self, token: String) -> Cookie<'static> {
+        Cookie::build((SESSION_COOKIE, token))
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .secure(self.secure_cookies)
+            .max_age(time::Duration::days(SESSION_DAYS))
+            .build()
+    }
+}
+
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default().hash_password(password.as_bytes(), &salt)?;
+    Ok(hash.to_string())
+}
+
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    PasswordHash::new(password_hash)
+        .and_then(|hash| Argon2::default().verify_password(password.as_bytes(), &hash))
+        .is_ok()
+}
+
+/// Checked in place of the hash of an unknown username, signing in then takes as long as
+/// with a wrong password and doesn't tell which usernames exist.
+pub fn dummy_password_hash() -> &'static str {
+    static DUMMY_HASH: LazyLock<String> =
+        LazyLock::new(|| hash_password("not a password").expect("dummy password is hashed"));
+    &DUMMY_HASH
+}
+
+pub fn new_session_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Resolves the session cookie into an [`Actor`] for the handlers, anonymous requests are
+/// sent to the login page.
+pub async fn require_actor(
+    State(users): State<UserStore>,
+    jar: CookieJar,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let user = match jar.get(SESSION_COOKIE) {
+        Some(cookie) => users.read_session_user(cookie.value()).await,
+        None => Ok(None),
+    };
+
+    match user {
+        Ok(Some(user)) => {
+            request.extensions_mut().insert(Actor::from(user));
+            next.run(request).await
+        }
+        Ok(None) => unauthorized(&request),
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read session");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to read session").into_response()
+        }
+    }
+}
+
+fn unauthorized(request: &Request) -> Response {
+    if request.headers().contains_key("hx-request") {
+        // htmx would swap a redirect into the page, let it navigate instead
+        (StatusCode::UNAUTHORIZED, [("hx-redirect", "/login")]).into_response()
+    } else if request.uri().path().starts_with("/api/") {
+        ApiError::unauthorized().into_response()
+    } else {
+        Redirect::to("/login").into_response()
+    }
+}
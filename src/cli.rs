@@ -0,0 +1,27 @@
+use crate::auth;
+use crate::store::UserStore;
+
+/// Maintenance commands given after the config path, e.g.
+/// `pantry config.toml create-user <username> <household>` with the password on stdin.
+pub async fn run(command: &str, mut args: impl Iterator<Item = String>, users: UserStore) {
+    match command {
+        "create-user" => {
+            let username = args.next().expect("no username provided");
+            let household = args.next().expect("no household provided");
+            let mut password = String::new();
+            std::io::stdin()
+                .read_line(&mut password)
+                .expect("failed to read password from stdin");
+            let password = password.trim_end_matches(['\r', '\n']);
+            assert!(!password.is_empty(), "password must not be empty");
+
+            let password_hash = auth::hash_password(password).expect("failed to hash password");
+            let id = users
+                .create_user(&username, &password_hash, &household)
+                .await
+                .expect("failed to create user");
+            println!("created user {username} ({id}) in household {household}");
+        }
+        command => panic!("unknown command {command}, expected create-user"),
+    }
+}
@@ -4,15 +4,55 @@ use serde::Deserialize;
 pub struct Configuration {
     pub database: DatabaseConfiguration,
     pub server: ServerConfiguration,
+    /// Item states next to the built-in stock and shopping, see `states`
+    #[serde(default)]
+    pub states: Vec<StateConfiguration>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct DatabaseConfiguration {
+    /// `sqlite://...` or `postgres://...`, the scheme picks the store
     pub dsn: String,
 }
 
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+impl DatabaseConfiguration {
+    /// The store backend named by the scheme of the DSN, `None` for schemes we don't support.
+    pub fn backend(&self) -> Option<Backend> {
+        let (scheme, _) = self.dsn.split_once(':')?;
+        match scheme {
+            "sqlite" => Some(Backend::Sqlite),
+            "postgres" | "postgresql" => Some(Backend::Postgres),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ServerConfiguration {
     pub host: String,
     pub port: u16,
+    /// Mark the session cookie `Secure`, enable when served over https
+    #[serde(default)]
+    pub secure_cookies: bool,
+}
+
+/// One `[[states]]` table. Naming a built-in state by its id changes how it is shown and where
+/// its items can move, any other id adds a state.
+#[derive(Deserialize, Debug)]
+pub struct StateConfiguration {
+    /// Stored with the item, keep it once items are in the state
+    pub id: i64,
+    /// Names the state in urls and forms, lowercase letters, digits and dashes
+    pub slug: String,
+    pub name: Option<String>,
+    /// Bootstrap classes for the card header, e.g. `bg-info`
+    pub css_color: Option<String>,
+    /// Slugs of the states its items can be moved to
+    pub transitions: Option<Vec<String>>,
 }
@@ -0,0 +1,42 @@
+use crate::{actor::Actor, error_fragment, item::Unit, recipe::Ingredient, store::RecipeStore};
+use axum::{
+    Form, debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct CreateIngredientForm {
+    item: i64,
+    quantity: f64,
+    unit: Unit,
+}
+
+#[debug_handler]
+pub async fn create_ingredient(
+    State(pool): State<RecipeStore>,
+    actor: Actor,
+    Path(recipe_id): Path<i64>,
+    Form(form): Form<CreateIngredientForm>,
+) -> impl IntoResponse {
+    if !form.quantity.is_finite() || form.quantity <= 0.0 {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "Enter a quantity above 0").into_response();
+    }
+    let ingredient = Ingredient {
+        id: 0,
+        recipe_id,
+        item_id: form.item,
+        name: String::new(),
+        quantity: form.quantity,
+        unit: form.unit,
+    };
+    match pool.create_ingredient(ingredient, &actor).await {
+        Ok(_) => StatusCode::CREATED.into_response(),
+        Err(err) => {
+            tracing::error!(err = %err, recipe_id, "failed to create ingredient");
+            error_fragment::store_error(&err, "Failed to create ingredient")
+        }
+    }
+}
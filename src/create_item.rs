@@ -1,27 +1,155 @@
-use crate::{item::Item, store::ItemStore};
-use axum::{Form, debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use crate::{
+    actor::Actor,
+    duplicates, error_fragment,
+    item::{Item, Quantity},
+    item_validation::{self, ItemFormErrors},
+    live_updates::LiveUpdates,
+    store::ItemStore,
+};
+use askama::Template;
+use axum::{
+    Form, debug_handler,
+    extract::{State, rejection::FormRejection},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
 use serde::Deserialize;
 
+/// What to do when an item of the same name already is in the list, asked for by
+/// [`DuplicateItemTemplate`].
+#[derive(Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum DuplicateChoice {
+    /// Add the quantity to the existing item
+    Merge,
+    /// Create the item anyway
+    Keep,
+}
+
 #[derive(Deserialize)]
 pub struct CreateItemForm {
     name: String,
     quantity: f64,
+    unit: crate::item::Unit,
+    state: crate::item::State,
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    location: Option<i64>,
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    expires_on: Option<chrono::NaiveDate>,
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    par_quantity: Option<f64>,
+    /// Set by the buttons of the duplicate warning, unset the first time the form is sent
+    #[serde(default)]
+    duplicate: Option<DuplicateChoice>,
+}
+
+/// Shown in the add modal when the item looks like one already in the list.
+#[derive(Template)]
+#[template(path = "duplicate_item.html")]
+struct DuplicateItemTemplate {
+    name: String,
     state: crate::item::State,
+    quantity: Quantity,
+    added: Quantity,
+    mergeable: bool,
 }
 
-#[debug_handler]
+#[debug_handler(state = crate::AppState)]
 pub async fn create_item(
     State(pool): State<ItemStore>,
-    Form(form): Form<CreateItemForm>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    form: Result<Form<CreateItemForm>, FormRejection>,
 ) -> impl IntoResponse {
-    match pool
-        .create(Item::new(0, form.name, form.quantity, form.state))
-        .await
-    {
-        Ok(_) => StatusCode::CREATED.into_response(),
+    let form = match form {
+        Ok(Form(form)) => form,
+        Err(rejection) => return ItemFormErrors::from_rejection(rejection).into_response(),
+    };
+    let item = Item::new(0, form.name, form.quantity, form.unit, form.state)
+        .with_location(form.location)
+        .with_expiry(form.expires_on)
+        .with_par(form.par_quantity);
+    let item = match item_validation::validate(item, None) {
+        Ok(item) => item,
+        Err(errors) => return errors.into_response(),
+    };
+
+    if form.duplicate != Some(DuplicateChoice::Keep) {
+        let existing = match duplicates::find_duplicate(&pool, &item.name, item.state, &actor).await
+        {
+            Ok(existing) => existing,
+            Err(err) => {
+                tracing::error!(err = %err, "failed to look for duplicates");
+                return error_fragment::store_error(&err, "Failed to create item");
+            }
+        };
+        if let Some(existing) = existing {
+            let added = item.measure().convert(existing.unit);
+            return match (form.duplicate, added) {
+                (Some(DuplicateChoice::Merge), Some(added)) => {
+                    merge(&pool, &live, &actor, &existing, added).await
+                }
+                _ => (
+                    StatusCode::CONFLICT,
+                    HtmlTemplate(DuplicateItemTemplate {
+                        name: existing.name.clone(),
+                        state: existing.state,
+                        quantity: existing.measure().normalized(),
+                        added: item.measure(),
+                        mergeable: added.is_some(),
+                    }),
+                )
+                    .into_response(),
+            };
+        }
+    }
+
+    let state = item.state;
+    match pool.create(item, &actor).await {
+        Ok(_) => {
+            live.publish(&actor, &[state]);
+            StatusCode::CREATED.into_response()
+        }
         Err(err) => {
             tracing::error!(err = %err, "failed to create item");
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to create item").into_response()
+            error_fragment::store_error(&err, "Failed to create item")
+        }
+    }
+}
+
+async fn merge(
+    pool: &ItemStore,
+    live: &LiveUpdates,
+    actor: &Actor,
+    existing: &Item,
+    added: Quantity,
+) -> Response {
+    match pool.restock(existing.id, added.amount, actor).await {
+        Ok(_) => {
+            live.publish(actor, &[existing.state]);
+            StatusCode::OK.into_response()
+        }
+        Err(err) => {
+            tracing::error!(err = %err, id = existing.id, "failed to merge into existing item");
+            error_fragment::store_error(&err, "Failed to add to the existing item")
+        }
+    }
+}
+
+struct HtmlTemplate<T>(T);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
         }
     }
 }
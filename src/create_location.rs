@@ -0,0 +1,28 @@
+use crate::{actor::Actor, error_fragment, location::Location, store::LocationStore};
+use axum::{Form, debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct CreateLocationForm {
+    name: String,
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    parent: Option<i64>,
+}
+
+#[debug_handler]
+pub async fn create_location(
+    State(pool): State<LocationStore>,
+    actor: Actor,
+    Form(form): Form<CreateLocationForm>,
+) -> impl IntoResponse {
+    match pool
+        .create_location(Location::new(0, form.name, form.parent), &actor)
+        .await
+    {
+        Ok(_) => StatusCode::CREATED.into_response(),
+        Err(err) => {
+            tracing::error!(err = %err, "failed to create location");
+            error_fragment::store_error(&err, "Failed to create location")
+        }
+    }
+}
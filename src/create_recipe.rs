@@ -0,0 +1,23 @@
+use crate::{actor::Actor, error_fragment, store::RecipeStore};
+use axum::{Form, debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct CreateRecipeForm {
+    name: String,
+}
+
+#[debug_handler]
+pub async fn create_recipe(
+    State(pool): State<RecipeStore>,
+    actor: Actor,
+    Form(form): Form<CreateRecipeForm>,
+) -> impl IntoResponse {
+    match pool.create_recipe(&form.name, &actor).await {
+        Ok(_) => StatusCode::CREATED.into_response(),
+        Err(err) => {
+            tracing::error!(err = %err, "failed to create recipe");
+            error_fragment::store_error(&err, "Failed to create recipe")
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use crate::store::ItemStore;
+use crate::{
+    actor::Actor, error_fragment, item::State as ItemState, live_updates::LiveUpdates,
+    store::ItemStore,
+};
 use axum::{
     debug_handler,
     extract::{Path, State},
@@ -6,13 +9,21 @@ use axum::{
     response::IntoResponse,
 };
 
-#[debug_handler]
-pub async fn delete_item(State(pool): State<ItemStore>, Path(id): Path<i64>) -> impl IntoResponse {
-    match pool.delete(id).await {
-        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+#[debug_handler(state = crate::AppState)]
+pub async fn delete_item(
+    State(pool): State<ItemStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match pool.delete(id, &actor).await {
+        Ok(_) => {
+            live.publish(&actor, ItemState::all());
+            StatusCode::NO_CONTENT.into_response()
+        }
         Err(err) => {
-            tracing::error!(err = %err, "failed to update item");
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update item").into_response()
+            tracing::error!(err = %err, id, "failed to delete item");
+            error_fragment::store_error(&err, "Failed to delete item")
         }
     }
 }
@@ -0,0 +1,22 @@
+use crate::{actor::Actor, error_fragment, store::LocationStore};
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+#[debug_handler]
+pub async fn delete_location(
+    State(pool): State<LocationStore>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match pool.delete_location(id, &actor).await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => {
+            tracing::error!(err = %err, "failed to delete location");
+            error_fragment::store_error(&err, "Failed to delete location")
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::{actor::Actor, error_fragment, store::MealPlanStore};
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+#[debug_handler]
+pub async fn delete_planned_meal(
+    State(pool): State<MealPlanStore>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match pool.delete_planned_meal(id, &actor).await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => {
+            tracing::error!(err = %err, "failed to delete planned meal");
+            error_fragment::store_error(&err, "Failed to delete planned meal")
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::{actor::Actor, error_fragment, store::RecipeStore};
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+#[debug_handler]
+pub async fn delete_recipe(
+    State(pool): State<RecipeStore>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match pool.delete_recipe(id, &actor).await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(err) => {
+            tracing::error!(err = %err, "failed to delete recipe");
+            error_fragment::store_error(&err, "Failed to delete recipe")
+        }
+    }
+}
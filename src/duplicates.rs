@@ -0,0 +1,164 @@
+use crate::actor::Actor;
+use crate::error_fragment;
+use crate::item::{Item, Quantity, State as ItemState, duplicate_key};
+use crate::live_updates::LiveUpdates;
+use crate::store::{ItemStore, StoreError};
+use askama::Template;
+use axum::response::{Html, Response};
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use std::collections::BTreeMap;
+
+/// Items in the same state whose names only differ in case, spacing or a plural, oldest first.
+pub fn duplicate_groups(items: Vec<Item>) -> Vec<Vec<Item>> {
+    let mut groups: BTreeMap<(i64, String), Vec<Item>> = BTreeMap::new();
+    for item in items {
+        let key = (item.state.id(), duplicate_key(&item.name));
+        groups.entry(key).or_default().push(item);
+    }
+    groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|mut group| {
+            group.sort_by_key(|item| item.id);
+            group
+        })
+        .collect()
+}
+
+/// The oldest item in `state` that `name` duplicates.
+pub async fn find_duplicate(
+    pool: &ItemStore,
+    name: &str,
+    state: ItemState,
+    actor: &Actor,
+) -> Result<Option<Item>, StoreError> {
+    let key = duplicate_key(name);
+    let items = pool.read_many_from_state(state, actor).await?;
+    Ok(items
+        .into_iter()
+        .filter(|item| duplicate_key(&item.name) == key)
+        .min_by_key(|item| item.id))
+}
+
+struct DuplicateGroupTemplate {
+    /// The oldest item, the others are merged into it
+    into: i64,
+    name: String,
+    state: ItemState,
+    items: Vec<(String, Quantity)>,
+    /// Quantities can only be added up when all units measure the same thing
+    mergeable: bool,
+}
+
+impl DuplicateGroupTemplate {
+    fn new(group: &[Item]) -> Self {
+        let (first, others) = group.split_first().expect("groups hold two items or more");
+        Self {
+            into: first.id,
+            name: first.name.clone(),
+            state: first.state,
+            items: group
+                .iter()
+                .map(|item| (item.name.clone(), item.measure().normalized()))
+                .collect(),
+            mergeable: first.merged_with(others).is_some(),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "duplicates.html")]
+struct DuplicatesTemplate {
+    groups: Vec<DuplicateGroupTemplate>,
+}
+
+async fn read_all(pool: &ItemStore, actor: &Actor) -> Result<Vec<Item>, StoreError> {
+    let mut items = Vec::new();
+    for state in ItemState::all() {
+        items.extend(pool.read_many_from_state(*state, actor).await?);
+    }
+    Ok(items)
+}
+
+async fn render(pool: &ItemStore, actor: &Actor) -> Response {
+    match read_all(pool, actor).await {
+        Ok(items) => {
+            let groups = duplicate_groups(items)
+                .iter()
+                .map(|group| DuplicateGroupTemplate::new(group))
+                .collect();
+            HtmlTemplate(DuplicatesTemplate { groups }).into_response()
+        }
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read items");
+            error_fragment::store_error(&err, "Failed to look for duplicates")
+        }
+    }
+}
+
+#[debug_handler]
+pub async fn list_duplicates(State(pool): State<ItemStore>, actor: Actor) -> impl IntoResponse {
+    render(&pool, &actor).await
+}
+
+/// Merges the items `id` is the oldest duplicate of into it and answers with the remaining
+/// duplicates.
+#[debug_handler(state = crate::AppState)]
+pub async fn merge_duplicates(
+    State(pool): State<ItemStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let into = match pool.read(id, &actor).await {
+        Ok(into) => into,
+        Err(err) => {
+            tracing::error!(err = %err, id, "failed to read item to merge into");
+            return error_fragment::store_error(&err, "Failed to merge duplicates");
+        }
+    };
+    let group = match pool.read_many_from_state(into.state, &actor).await {
+        Ok(items) => duplicate_groups(items)
+            .into_iter()
+            .find(|group| group[0].id == id),
+        Err(err) => {
+            tracing::error!(err = %err, id, "failed to read duplicates");
+            return error_fragment::store_error(&err, "Failed to merge duplicates");
+        }
+    };
+    // Someone else merged them already, or the names changed since the list was shown
+    let Some(group) = group else {
+        return render(&pool, &actor).await;
+    };
+
+    let from: Vec<i64> = group[1..].iter().map(|item| item.id).collect();
+    if let Err(err) = pool.merge(id, &from, &actor).await {
+        tracing::error!(err = %err, id, "failed to merge duplicates");
+        return error_fragment::store_error(&err, "Failed to merge duplicates");
+    }
+    live.publish(&actor, &[into.state]);
+    render(&pool, &actor).await
+}
+
+struct HtmlTemplate<T>(T);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
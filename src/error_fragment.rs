@@ -0,0 +1,45 @@
+use crate::store::StoreError;
+use askama::Template;
+use axum::{
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+
+#[derive(Template)]
+#[template(path = "state_items_error.html")]
+struct ErrorFragmentTemplate {
+    error_message: String,
+}
+
+/// Alert fragment for a failed store operation. `failed` says what the user tried, the
+/// status and the rest of the message follow from the kind of error.
+pub fn store_error(err: &StoreError, failed: &str) -> Response {
+    let (status, error_message) = match err {
+        StoreError::NotFound => (
+            StatusCode::NOT_FOUND,
+            format!("{failed}, it no longer exists."),
+        ),
+        StoreError::Conflict => (
+            StatusCode::CONFLICT,
+            format!("{failed}, it was changed in the meantime. Reload and try again."),
+        ),
+        StoreError::Validation(_) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("{failed}, some of its values are not allowed."),
+        ),
+        StoreError::Unavailable => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("{failed}, the database can't be reached. Try again in a moment."),
+        ),
+        StoreError::SqlError(_) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{failed}.")),
+    };
+
+    match (ErrorFragmentTemplate { error_message }).render() {
+        Ok(html) => (status, Html(html)).into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to render template. Error: {err}"),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,87 @@
+use crate::actor::Actor;
+use crate::state_items::ItemTemplate;
+use crate::store::ItemStore;
+use askama::Template;
+use axum::extract::Query;
+use axum::response::{Html, Response};
+use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+
+const DAY_OPTIONS: &[i64] = &[3, 7, 14, 30];
+
+#[derive(Template)]
+#[template(path = "expiring_items.html")]
+struct ExpiringItemsTemplate {
+    days: i64,
+    day_options: &'static [i64],
+    items: Vec<ItemTemplate>,
+}
+
+#[derive(Template)]
+#[template(path = "state_items_error.html")]
+struct ExpiringItemsErrorTemplate {
+    error_message: String,
+}
+
+fn default_days() -> i64 {
+    7
+}
+
+#[derive(Deserialize)]
+pub struct QueryParameters {
+    #[serde(default = "default_days")]
+    days: i64,
+}
+
+#[debug_handler]
+pub async fn expiring_items(
+    State(pool): State<ItemStore>,
+    actor: Actor,
+    Query(query): Query<QueryParameters>,
+) -> impl IntoResponse {
+    let today = chrono::Local::now().date_naive();
+    let Some(until) = today.checked_add_days(chrono::Days::new(query.days.max(0) as u64)) else {
+        let template = ExpiringItemsErrorTemplate {
+            error_message: format!("{} days from today is past the calendar.", query.days),
+        };
+        return HtmlTemplate(template, StatusCode::UNPROCESSABLE_ENTITY).into_response();
+    };
+    let items = match pool.read_expiring_until(until, &actor).await {
+        Ok(items) => items,
+        Err(err) => {
+            tracing::error!(err = %err, days = query.days, "failed to read expiring items");
+            let template = ExpiringItemsErrorTemplate {
+                error_message: "Failed to get expiring items.".to_string(),
+            };
+            return HtmlTemplate(template, StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let template = ExpiringItemsTemplate {
+        days: query.days,
+        day_options: DAY_OPTIONS,
+        items: items
+            .iter()
+            .map(|item| ItemTemplate::new(item, today))
+            .collect(),
+    };
+    HtmlTemplate(template, StatusCode::OK).into_response()
+}
+
+struct HtmlTemplate<T>(T, StatusCode);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => (self.1, Html(html)).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use crate::actor::Actor;
+use crate::error_fragment;
+use crate::inventory_csv::InventoryRecord;
+use crate::item::{Item, State as ItemState};
+use crate::store::{ItemStore, LocationStore};
+use axum::http::header;
+use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use std::collections::HashMap;
+
+#[debug_handler(state = crate::AppState)]
+pub async fn export_inventory(
+    State(pool): State<ItemStore>,
+    State(locations): State<LocationStore>,
+    actor: Actor,
+) -> impl IntoResponse {
+    let mut items = Vec::new();
+    for &state in ItemState::all() {
+        match pool.read_many_from_state(state, &actor).await {
+            Ok(mut found) => items.append(&mut found),
+            Err(err) => {
+                tracing::error!(err = %err, state = %state, "failed to read items for export");
+                return error_fragment::store_error(&err, "Failed to get items");
+            }
+        }
+    }
+    let locations = match locations.read_locations(&actor).await {
+        Ok(locations) => locations,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read locations for export");
+            return error_fragment::store_error(&err, "Failed to get locations");
+        }
+    };
+    let paths = crate::location::paths(&locations);
+
+    match render_csv(&items, &paths) {
+        Ok(body) => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"inventory.csv\"",
+                ),
+            ],
+            body,
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to render inventory. Error: {err}"),
+        )
+            .into_response(),
+    }
+}
+
+fn render_csv(
+    items: &[Item],
+    paths: &HashMap<i64, String>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    // The header is written by hand so an empty inventory still gets one
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(false)
+        .from_writer(Vec::new());
+    writer.write_record(InventoryRecord::HEADER)?;
+    for item in items {
+        writer.serialize(InventoryRecord::from_item(item, paths))?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
@@ -0,0 +1,139 @@
+use crate::actor::Actor;
+use crate::error_fragment;
+use crate::item::{Item, State as ItemState};
+use crate::store::ItemStore;
+use askama::Template;
+use axum::extract::Query;
+use axum::http::{HeaderMap, header};
+use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[serde(alias = "md")]
+    Markdown,
+    #[serde(alias = "txt")]
+    Text,
+    Csv,
+}
+
+impl ExportFormat {
+    /// Picks the first format the `Accept` header names, Markdown when it names none.
+    fn from_accept(headers: &HeaderMap) -> Self {
+        let accept = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        accept
+            .split(',')
+            .filter_map(|media_type| {
+                match media_type.split(';').next().unwrap_or_default().trim() {
+                    "text/markdown" => Some(Self::Markdown),
+                    "text/plain" => Some(Self::Text),
+                    "text/csv" => Some(Self::Csv),
+                    _ => None,
+                }
+            })
+            .next()
+            .unwrap_or(Self::Markdown)
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Text => "text/plain; charset=utf-8",
+            Self::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Text => "txt",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "shopping_list.md")]
+struct MarkdownTemplate<'a> {
+    items: &'a [Item],
+}
+
+#[derive(Template)]
+#[template(path = "shopping_list.txt")]
+struct TextTemplate<'a> {
+    items: &'a [Item],
+}
+
+#[derive(Deserialize)]
+pub struct QueryParameters {
+    format: Option<ExportFormat>,
+}
+
+#[debug_handler]
+pub async fn export_shopping(
+    State(pool): State<ItemStore>,
+    actor: Actor,
+    headers: HeaderMap,
+    Query(query): Query<QueryParameters>,
+) -> impl IntoResponse {
+    let format = query
+        .format
+        .unwrap_or_else(|| ExportFormat::from_accept(&headers));
+
+    let mut items = match pool.read_many_from_state(ItemState::SHOPPING, &actor).await {
+        Ok(items) => items,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read shopping items for export");
+            return error_fragment::store_error(&err, "Failed to get shopping items");
+        }
+    };
+    items.sort_by_key(|item| item.name.to_lowercase());
+
+    let body = match format {
+        ExportFormat::Markdown => MarkdownTemplate { items: &items }
+            .render()
+            .map_err(|err| err.to_string()),
+        ExportFormat::Text => TextTemplate { items: &items }
+            .render()
+            .map_err(|err| err.to_string()),
+        ExportFormat::Csv => render_csv(&items).map_err(|err| err.to_string()),
+    };
+    match body {
+        Ok(body) => (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!(
+                        "attachment; filename=\"shopping-list.{}\"",
+                        format.extension()
+                    ),
+                ),
+            ],
+            body,
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to render shopping list. Error: {err}"),
+        )
+            .into_response(),
+    }
+}
+
+fn render_csv(items: &[Item]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(["name", "quantity", "unit"])?;
+    for item in items {
+        writer.write_record([
+            item.name.as_str(),
+            &item.quantity.to_string(),
+            &item.unit.to_string(),
+        ])?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Deserializer};
+use std::{fmt::Display, str::FromStr};
+
+/// Html forms submit an empty string for unset optional fields, treat it as `None`.
+pub fn empty_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    let value = Option::<String>::deserialize(deserializer)?;
+    match value.as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(value) => value.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
@@ -0,0 +1,864 @@
+//! The item handlers driven through the full router, on top of the in-memory store.
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Method, Request, StatusCode, header},
+};
+use chrono::{Duration, Utc};
+use http_body_util::BodyExt;
+use tower::ServiceExt;
+
+use crate::actor::Actor;
+use crate::auth::{SESSION_COOKIE, SessionSettings};
+use crate::item::{Item, State, Unit};
+use crate::memory_store::MemoryItemStore;
+use crate::purchase::{Price, Purchase};
+use crate::store::{Carts, Purchases, Recipes, Shops, Store, StoreError, Users};
+use crate::{AppState, router};
+
+const SESSION: &str = "handler-tests-session";
+
+struct TestApp {
+    store: MemoryItemStore,
+    router: Router,
+    actor: Actor,
+}
+
+impl TestApp {
+    /// App with a signed-in user, requests carry their session cookie.
+    async fn new() -> Self {
+        let store = MemoryItemStore::default();
+        let user_id = store.create_user("alice", "unused", "Home").await.unwrap();
+        store
+            .create_session(SESSION, user_id, Utc::now().naive_utc() + Duration::days(1))
+            .await
+            .unwrap();
+        let user = store.read_user_by_name("alice").await.unwrap().unwrap();
+        let state = AppState::new(
+            store.clone(),
+            SessionSettings {
+                secure_cookies: false,
+            },
+        );
+
+        Self {
+            store,
+            router: router(state),
+            actor: Actor::from(user),
+        }
+    }
+
+    async fn stock(&self, name: &str, quantity: f64) -> i64 {
+        self.store
+            .create(
+                Item::new(0, name.to_string(), quantity, Unit::Piece, State::STOCK),
+                &self.actor,
+            )
+            .await
+            .unwrap()
+    }
+
+    async fn get(&self, uri: &str) -> (StatusCode, String) {
+        self.send(Method::GET, uri, None).await
+    }
+
+    async fn send(&self, method: Method, uri: &str, form: Option<&str>) -> (StatusCode, String) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::COOKIE, format!("{SESSION_COOKIE}={SESSION}"));
+        if form.is_some() {
+            request = request.header(header::CONTENT_TYPE, "application/x-www-form-urlencoded");
+        }
+        let request = request
+            .body(Body::from(form.unwrap_or_default().to_string()))
+            .unwrap();
+        send(&self.router, request).await
+    }
+}
+
+async fn send(router: &Router, request: Request<Body>) -> (StatusCode, String) {
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+fn unavailable() -> StoreError {
+    sqlx::Error::PoolTimedOut.into()
+}
+
+#[tokio::test]
+async fn requests_without_a_session_go_to_the_login() {
+    let app = TestApp::new().await;
+
+    let response = app
+        .router
+        .clone()
+        .oneshot(Request::get("/").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SEE_OTHER);
+    assert_eq!(response.headers()[header::LOCATION], "/login");
+
+    let request = Request::get("/item?state=stock")
+        .header("hx-request", "true")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()["hx-redirect"], "/login");
+}
+
+#[tokio::test]
+async fn login_answers_an_unknown_username_like_a_wrong_password() {
+    let app = TestApp::new().await;
+
+    let (status, unknown) = app
+        .send(Method::POST, "/login", Some("username=bob&password=secret"))
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, wrong) = app
+        .send(
+            Method::POST,
+            "/login",
+            Some("username=alice&password=secret"),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(unknown.contains("Unknown username or wrong password."));
+    assert_eq!(unknown.replace("bob", "alice"), wrong);
+}
+
+#[tokio::test]
+async fn create_item_stores_the_item() {
+    let app = TestApp::new().await;
+
+    let (status, _) = app
+        .send(
+            Method::POST,
+            "/item",
+            Some("name=Milk&quantity=2&unit=l&state=stock&par_quantity=1&location="),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let stock = app
+        .store
+        .read_many_from_state(State::STOCK, &app.actor)
+        .await
+        .unwrap();
+    assert_eq!(stock.len(), 1);
+    assert_eq!(stock[0].name, "Milk");
+    assert_eq!(stock[0].unit, Unit::Litre);
+    assert_eq!(stock[0].par_quantity, Some(1.0));
+    assert_eq!(stock[0].location_id, None);
+}
+
+#[tokio::test]
+async fn create_item_rejects_an_incomplete_form() {
+    let app = TestApp::new().await;
+
+    let (status, _) = app
+        .send(Method::POST, "/item", Some("name=Milk&unit=l&state=stock"))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn create_item_points_out_invalid_fields() {
+    let app = TestApp::new().await;
+
+    let (status, body) = app
+        .send(
+            Method::POST,
+            "/item",
+            Some("name=++&quantity=NaN&unit=l&state=stock&par_quantity=-1"),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.contains(r#"data-field="name""#));
+    assert!(body.contains(r#"data-field="quantity""#));
+    assert!(body.contains(r#"data-field="par_quantity""#));
+
+    let long_name = "a".repeat(crate::item_validation::NAME_MAX_CHARS + 1);
+    let form = format!("name={long_name}&quantity=1&unit=l&state=stock");
+    let (status, body) = app.send(Method::POST, "/item", Some(&form)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.contains("at most"));
+
+    let stock = app
+        .store
+        .read_many_from_state(State::STOCK, &app.actor)
+        .await
+        .unwrap();
+    assert!(stock.is_empty());
+}
+
+#[tokio::test]
+async fn create_item_offers_to_merge_a_duplicate() {
+    let app = TestApp::new().await;
+    let id = app.stock("Tomato", 2.0).await;
+
+    let form = "name=tomatoes+&quantity=3&unit=pcs&state=stock";
+    let (status, body) = app.send(Method::POST, "/item", Some(form)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body.contains(r#"value="merge""#));
+    assert_eq!(app.store.read(id, &app.actor).await.unwrap().quantity, 2.0);
+
+    let merge = format!("{form}&duplicate=merge");
+    let (status, _) = app.send(Method::POST, "/item", Some(&merge)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(app.store.read(id, &app.actor).await.unwrap().quantity, 5.0);
+
+    let keep = format!("{form}&duplicate=keep");
+    let (status, _) = app.send(Method::POST, "/item", Some(&keep)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let stock = app
+        .store
+        .read_many_from_state(State::STOCK, &app.actor)
+        .await
+        .unwrap();
+    assert_eq!(stock.len(), 2);
+}
+
+#[tokio::test]
+async fn duplicates_are_listed_and_merged() {
+    let app = TestApp::new().await;
+    let into = app.stock("Egg", 6.0).await;
+    let from = app.stock(" eggs", 4.0).await;
+    app.stock("Eggplant", 1.0).await;
+
+    let (status, body) = app.get("/item/duplicates").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains(&format!("/item/{into}/merge-duplicates")));
+    assert!(!body.contains("Eggplant"));
+
+    let uri = format!("/item/{into}/merge-duplicates");
+    let (status, body) = app.send(Method::POST, &uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("No likely duplicates"));
+    assert_eq!(
+        app.store.read(into, &app.actor).await.unwrap().quantity,
+        10.0
+    );
+    assert!(app.store.read(from, &app.actor).await.is_err());
+}
+
+#[tokio::test]
+async fn create_item_reports_store_failures() {
+    let app = TestApp::new().await;
+    app.store.fail_with(unavailable);
+
+    let (status, body) = app
+        .send(
+            Method::POST,
+            "/item",
+            Some("name=Milk&quantity=2&unit=l&state=stock"),
+        )
+        .await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body.contains("Failed to create item, the database can&#39;t be reached"));
+}
+
+#[tokio::test]
+async fn create_item_rejects_a_missing_location() {
+    let app = TestApp::new().await;
+
+    let (status, body) = app
+        .send(
+            Method::POST,
+            "/item",
+            Some("name=Milk&quantity=2&unit=l&state=stock&location=42"),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body.contains("Failed to create item"));
+}
+
+#[tokio::test]
+async fn state_items_lists_the_items_of_the_state() {
+    let app = TestApp::new().await;
+    app.stock("Milk", 2.0).await;
+    app.store
+        .create(
+            Item::new(0, "Bread".to_string(), 1.0, Unit::Piece, State::SHOPPING),
+            &app.actor,
+        )
+        .await
+        .unwrap();
+
+    let (status, body) = app.get("/item?state=stock").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("Milk"));
+    assert!(!body.contains("Bread"));
+
+    let (status, body) = app.get("/item?state=shopping&q=bred").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("Bread"));
+}
+
+#[tokio::test]
+async fn state_items_pages_past_names_with_spaces() {
+    let app = TestApp::new().await;
+    for n in 1..=30 {
+        app.stock(&format!(" Rice {n:02} "), 1.0).await;
+    }
+
+    let (status, body) = app.get("/item?state=stock").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("Rice 25"));
+    assert!(!body.contains("Rice 26"));
+    let (_, after) = body.split_once("&after=").expect("a next page");
+    let (after, _) = after.split_once('"').unwrap();
+
+    let (status, body) = app.get(&format!("/item?state=stock&after={after}")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!body.contains("Rice 25"));
+    assert!(body.contains("Rice 26"));
+    assert!(body.contains("Rice 30"));
+}
+
+#[tokio::test]
+async fn expiring_items_rejects_days_past_the_calendar() {
+    let app = TestApp::new().await;
+
+    let (status, _) = app.get(&format!("/item/expiring?days={}", i64::MAX)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn create_ingredient_needs_a_quantity_above_zero() {
+    let app = TestApp::new().await;
+    let rice = app.stock("Rice", 1.0).await;
+    let recipe = app.store.create_recipe("Pilaf", &app.actor).await.unwrap();
+
+    for quantity in ["0", "-1", "inf", "NaN"] {
+        let form = format!("item={rice}&quantity={quantity}&unit=pcs");
+        let (status, _) = app
+            .send(
+                Method::POST,
+                &format!("/recipe/{recipe}/ingredient"),
+                Some(&form),
+            )
+            .await;
+        assert_eq!(
+            status,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "quantity {quantity}"
+        );
+    }
+    let recipe = app.store.read_recipe(recipe, &app.actor).await.unwrap();
+    assert!(recipe.ingredients.is_empty());
+}
+
+#[tokio::test]
+async fn recipe_shows_quantities_in_the_unit_that_reads_best() {
+    let app = TestApp::new().await;
+    let flour = app
+        .store
+        .create(
+            Item::new(0, "Flour".to_string(), 1200.0, Unit::Gram, State::STOCK),
+            &app.actor,
+        )
+        .await
+        .unwrap();
+    let recipe = app.store.create_recipe("Bread", &app.actor).await.unwrap();
+    let (status, _) = app
+        .send(
+            Method::POST,
+            &format!("/recipe/{recipe}/ingredient"),
+            Some(&format!("item={flour}&quantity=1.5&unit=kg")),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = app.get(&format!("/recipe/{recipe}")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("1.5 kg"));
+    assert!(body.contains("1.2 kg"));
+    assert!(body.contains("300 g missing"));
+}
+
+#[tokio::test]
+async fn index_shows_a_card_per_state() {
+    let app = TestApp::new().await;
+
+    let (status, body) = app.get("/").await;
+    assert_eq!(status, StatusCode::OK);
+    for state in State::all() {
+        assert!(body.contains(&format!(r#"hx-get="/item?state={state}""#)));
+        assert!(body.contains(&format!(r#"<option value="{state}">"#)));
+    }
+
+    let (status, _) = app.get("/item?state=frozen").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn state_items_reports_store_failures() {
+    let app = TestApp::new().await;
+    app.store.fail_with(unavailable);
+
+    let (status, body) = app.get("/item?state=stock").await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(body.contains("Failed to get items in"));
+}
+
+#[tokio::test]
+async fn get_update_item_renders_the_stored_item() {
+    let app = TestApp::new().await;
+    let id = app.stock("Milk", 2.0).await;
+
+    let (status, body) = app.get(&format!("/item/edit-form/{id}")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains(r#"value="Milk""#));
+    assert!(body.contains(r#"name="version" value="1""#));
+}
+
+#[tokio::test]
+async fn get_update_item_reports_store_failures() {
+    let app = TestApp::new().await;
+    let id = app.stock("Milk", 2.0).await;
+    app.store.fail_with(unavailable);
+
+    let (status, _) = app.get(&format!("/item/edit-form/{id}")).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn get_update_item_of_a_missing_item_is_not_found() {
+    let app = TestApp::new().await;
+
+    let (status, body) = app.get("/item/edit-form/42").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body.contains("it no longer exists"));
+}
+
+#[tokio::test]
+async fn update_item_saves_the_changes() {
+    let app = TestApp::new().await;
+    let id = app.stock("Milk", 2.0).await;
+
+    let form = format!("id={id}&name=Oat+milk&quantity=3&unit=l&state=stock&version=1");
+    let (status, _) = app.send(Method::PUT, "/item", Some(&form)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let item = app.store.read(id, &app.actor).await.unwrap();
+    assert_eq!(item.name, "Oat milk");
+    assert_eq!(item.quantity, 3.0);
+    assert_eq!(item.version, 2);
+}
+
+#[tokio::test]
+async fn update_item_trims_the_name_and_rejects_bad_quantities() {
+    let app = TestApp::new().await;
+    let id = app.stock("Milk", 2.0).await;
+
+    let form = format!("id={id}&name=Milk&quantity=-3&unit=l&state=stock&version=1");
+    let (status, body) = app.send(Method::PUT, "/item", Some(&form)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.contains(r#"data-field="quantity""#));
+    assert_eq!(app.store.read(id, &app.actor).await.unwrap().quantity, 2.0);
+
+    let form = format!(
+        "id={id}&name=++Oat+milk+&quantity=3&unit=l&state=shopping&original_state=stock&version=1"
+    );
+    let (status, _) = app.send(Method::PUT, "/item", Some(&form)).await;
+    assert_eq!(status, StatusCode::OK);
+    let item = app.store.read(id, &app.actor).await.unwrap();
+    assert_eq!(item.name, "Oat milk");
+    assert_eq!(item.state, State::SHOPPING);
+}
+
+#[tokio::test]
+async fn api_validates_items_like_the_forms() {
+    let app = TestApp::new().await;
+    let id = app.stock("Milk", 2.0).await;
+    let json = |method: Method, uri: &str, body: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::COOKIE, format!("{SESSION_COOKIE}={SESSION}"))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    };
+
+    let (status, body) = send(
+        &app.router,
+        json(
+            Method::POST,
+            "/api/v1/items",
+            r#"{"name": "  ", "quantity": 1, "state": "stock"}"#,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.contains("invalid_item"));
+
+    let (status, _) = send(
+        &app.router,
+        json(
+            Method::PUT,
+            &format!("/api/v1/items/{id}"),
+            r#"{"name": "Milk", "quantity": -1, "state": "stock"}"#,
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(app.store.read(id, &app.actor).await.unwrap().quantity, 2.0);
+}
+
+#[tokio::test]
+async fn api_rejects_an_expiry_window_past_the_calendar() {
+    let app = TestApp::new().await;
+
+    let (status, body) = app
+        .get(&format!("/api/v1/items?expiring_within={}", u64::MAX))
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.contains("invalid_query"));
+}
+
+#[tokio::test]
+async fn update_item_shows_both_versions_on_a_conflict() {
+    let app = TestApp::new().await;
+    let id = app.stock("Milk", 2.0).await;
+    let form = format!("id={id}&name=Milk&quantity=3&unit=pcs&state=stock&version=1");
+    app.send(Method::PUT, "/item", Some(&form)).await;
+
+    let stale = format!("id={id}&name=Milk&quantity=5&unit=pcs&state=stock&version=1");
+    let (status, body) = app.send(Method::PUT, "/item", Some(&stale)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body.contains("Saved version"));
+    assert!(body.contains("5 pcs"));
+    assert!(body.contains("3 pcs"));
+    assert_eq!(app.store.read(id, &app.actor).await.unwrap().quantity, 3.0);
+}
+
+#[tokio::test]
+async fn update_item_reports_store_failures() {
+    let app = TestApp::new().await;
+    let id = app.stock("Milk", 2.0).await;
+    app.store.fail_with(unavailable);
+
+    let form = format!("id={id}&name=Milk&quantity=3&unit=pcs&state=stock&version=1");
+    let (status, body) = app.send(Method::PUT, "/item", Some(&form)).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body.contains("Failed to update item"));
+
+    app.store.recover();
+    assert_eq!(app.store.read(id, &app.actor).await.unwrap().quantity, 2.0);
+}
+
+#[tokio::test]
+async fn update_item_of_a_missing_item_is_not_found() {
+    let app = TestApp::new().await;
+
+    let form = "id=42&name=Milk&quantity=3&unit=pcs&state=stock&version=1";
+    let (status, body) = app.send(Method::PUT, "/item", Some(form)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body.contains("Failed to update item, it no longer exists"));
+}
+
+#[tokio::test]
+async fn delete_item_removes_the_item() {
+    let app = TestApp::new().await;
+    let id = app.stock("Milk", 2.0).await;
+
+    let (status, _) = app.send(Method::DELETE, &format!("/item/{id}"), None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert!(app.store.read(id, &app.actor).await.is_err());
+}
+
+#[tokio::test]
+async fn delete_item_reports_store_failures() {
+    let app = TestApp::new().await;
+    let id = app.stock("Milk", 2.0).await;
+    app.store.fail_with(unavailable);
+
+    let (status, _) = app.send(Method::DELETE, &format!("/item/{id}"), None).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    app.store.recover();
+    assert!(app.store.read(id, &app.actor).await.is_ok());
+}
+
+#[tokio::test]
+async fn delete_item_of_a_missing_item_is_not_found() {
+    let app = TestApp::new().await;
+
+    let (status, body) = app.send(Method::DELETE, "/item/42", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body.contains("Failed to delete item, it no longer exists"));
+}
+
+#[tokio::test]
+async fn buying_an_item_records_its_price_for_the_reports() {
+    let app = TestApp::new().await;
+    let id = app
+        .store
+        .create(
+            Item::new(0, "Coffee".to_string(), 500.0, Unit::Gram, State::SHOPPING),
+            &app.actor,
+        )
+        .await
+        .unwrap();
+
+    let (_, body) = app.get(&format!("/item/edit-form/{id}")).await;
+    assert!(body.contains(r#"name="price""#));
+
+    let form = format!(
+        "id={id}&name=Coffee&quantity=500&unit=g&state=stock&original_state=shopping&price=4.555&shop=Corner+shop&version=1"
+    );
+    let (status, body) = app.send(Method::PUT, "/item", Some(&form)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.contains(r#"data-field="price""#));
+
+    let form = form.replace("price=4.555", "price=4%2C50");
+    let (status, _) = app.send(Method::PUT, "/item", Some(&form)).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app.get("/report").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("Coffee"));
+    assert!(body.contains("Corner shop"));
+    assert!(body.contains("4.50"));
+
+    let (status, body) = app.get("/report/prices?item=coffee").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("9.00 / kg"));
+}
+
+#[tokio::test]
+async fn import_validates_every_row_like_the_forms() {
+    let app = TestApp::new().await;
+    let long_name = "a".repeat(5000);
+    let csv = format!(
+        "name,quantity,state\nRice,2,stock\n{long_name},1,stock\n , 1 ,stock\nTea,-1,stock\n"
+    );
+    let body = format!(
+        "--boundary\r\nContent-Disposition: form-data; name=\"file\"; filename=\"inventory.csv\"\r\n\r\n{csv}\r\n--boundary--\r\n"
+    );
+    let request = Request::post("/inventory/import")
+        .header(header::COOKIE, format!("{SESSION_COOKIE}={SESSION}"))
+        .header(
+            header::CONTENT_TYPE,
+            "multipart/form-data; boundary=boundary",
+        )
+        .body(Body::from(body))
+        .unwrap();
+
+    let (status, body) = send(&app.router, request).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.contains("Line 3: Item Name: Use at most 100 characters"));
+    assert!(body.contains("Line 4: Item Name: Enter a name<"));
+    assert!(body.contains("Line 5: Quantity: Enter a number of at least 0"));
+    let stock = app
+        .store
+        .read_many_from_state(State::STOCK, &app.actor)
+        .await
+        .unwrap();
+    assert!(stock.is_empty());
+}
+
+#[tokio::test]
+async fn reports_add_up_huge_prices_without_overflowing() {
+    let app = TestApp::new().await;
+    let saffron = app.stock("Saffron", 1.0).await;
+    for _ in 0..2 {
+        let purchase = Purchase {
+            id: 0,
+            item_id: saffron,
+            name: "Saffron".to_string(),
+            quantity: 1.0,
+            unit: Unit::Gram,
+            price: Price(i64::MAX - 1),
+            shop: None,
+            purchased_on: Utc::now().date_naive(),
+        };
+        app.store
+            .record_purchase(purchase, &app.actor)
+            .await
+            .unwrap();
+    }
+
+    let (status, body) = app.get("/report").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains(&Price(i64::MAX).to_string()));
+}
+
+#[tokio::test]
+async fn reports_report_store_failures() {
+    let app = TestApp::new().await;
+    app.store.fail_with(unavailable);
+
+    let (status, body) = app.get("/report").await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert!(body.contains("Failed to get purchases"));
+}
+
+#[tokio::test]
+async fn shopping_list_follows_the_aisles_of_the_shop() {
+    let app = TestApp::new().await;
+    let mut ids = Vec::new();
+    for name in ["Soap", "Milk", "Bread"] {
+        let item = Item::new(0, name.to_string(), 1.0, Unit::Piece, State::SHOPPING);
+        ids.push(app.store.create(item, &app.actor).await.unwrap());
+    }
+    let [soap, milk, bread] = ids[..] else {
+        unreachable!()
+    };
+
+    let (status, body) = app.send(Method::POST, "/shop", Some("name=Market")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("Market"));
+    let shop = app.store.read_shops(&app.actor).await.unwrap()[0].id;
+    for aisle in ["Dairy", "Bakery"] {
+        let (status, _) = app
+            .send(
+                Method::POST,
+                &format!("/shop/{shop}/aisle"),
+                Some(&format!("name={aisle}")),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let aisles = app.store.read_shops(&app.actor).await.unwrap()[0]
+        .aisles
+        .clone();
+    let (dairy, bakery) = (aisles[0].id, aisles[1].id);
+    for (item, aisle) in [(milk, dairy), (bread, bakery)] {
+        let (status, _) = app
+            .send(
+                Method::PUT,
+                &format!("/shop/{shop}/item/{item}/aisle"),
+                Some(&format!("aisle={aisle}")),
+            )
+            .await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    // Items are set in bold, the aisles heading them follow their icon
+    let position = |body: &str, name: &str| {
+        body.find(&format!("<strong>{name}</strong>"))
+            .or_else(|| body.find(&format!("</i> {name}")))
+            .unwrap()
+    };
+    let (status, body) = app.get(&format!("/item?state=shopping&shop={shop}")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(position(&body, "Dairy") < position(&body, "Milk"));
+    assert!(position(&body, "Milk") < position(&body, "Bakery"));
+    assert!(position(&body, "Bread") < position(&body, "No aisle"));
+    assert!(position(&body, "No aisle") < position(&body, "Soap"));
+
+    let (status, _) = app
+        .send(
+            Method::POST,
+            &format!("/shop/aisle/{bakery}/move"),
+            Some("direction=up"),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = app.get(&format!("/item?state=shopping&shop={shop}")).await;
+    assert!(position(&body, "Bread") < position(&body, "Milk"));
+    assert!(position(&body, "Milk") < position(&body, "Soap"));
+
+    // Aisles of a shop that is gone
+    let (status, body) = app
+        .send(
+            Method::PUT,
+            &format!("/shop/{}/item/{soap}/aisle", shop + 100),
+            Some(&format!("aisle={dairy}")),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert!(body.contains("it no longer exists"));
+}
+
+#[tokio::test]
+async fn shopping_trip_puts_the_ticked_off_items_in_stock() {
+    let app = TestApp::new().await;
+    let rice = app.stock("Rice", 1.0).await;
+    let mut ids = Vec::new();
+    for name in ["Rice", "Tea"] {
+        let item = Item::new(0, name.to_string(), 2.0, Unit::Piece, State::SHOPPING);
+        ids.push(app.store.create(item, &app.actor).await.unwrap());
+    }
+    let [more_rice, tea] = ids[..] else {
+        unreachable!()
+    };
+
+    let (status, body) = app.get("/trip").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("Rice"));
+    assert!(body.contains("Tick items off"));
+
+    let (status, body) = app
+        .send(
+            Method::PUT,
+            &format!("/trip/item/{more_rice}/cart"),
+            Some("in_cart=true&shop="),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("Put 1 in Stock"));
+
+    let (status, body) = app
+        .send(
+            Method::PUT,
+            &format!("/trip/item/{more_rice}/quantity"),
+            Some("quantity=-1&version=1"),
+        )
+        .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(body.contains(r#"data-field="quantity""#));
+    let (status, _) = app
+        .send(
+            Method::PUT,
+            &format!("/trip/item/{more_rice}/quantity"),
+            Some("quantity=3&version=1"),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = app
+        .send(
+            Method::PUT,
+            &format!("/trip/item/{more_rice}/quantity"),
+            Some("quantity=4&version=1"),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body.contains("changed in the meantime"));
+    // The stock is not bought on the trip
+    let (status, _) = app
+        .send(
+            Method::PUT,
+            &format!("/trip/item/{rice}/quantity"),
+            Some("quantity=5&version=1"),
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = app
+        .send(Method::POST, "/trip/check-out", Some("shop="))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.contains("Tea"));
+    assert!(!body.contains("Rice"));
+    let rice = app.store.read(rice, &app.actor).await.unwrap();
+    assert_eq!(rice.quantity, 4.0);
+    let tea = app.store.read(tea, &app.actor).await.unwrap();
+    assert_eq!(tea.state, State::SHOPPING);
+    assert!(app.store.read_cart(&app.actor).await.unwrap().is_empty());
+    // Nothing was paid on the trip as far as the reports know
+    assert!(
+        app.store
+            .read_purchases(&app.actor)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
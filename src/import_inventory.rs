@@ -0,0 +1,205 @@
+use crate::actor::Actor;
+use crate::inventory_csv::InventoryRecord;
+use crate::item::Item;
+use crate::live_updates::LiveUpdates;
+use crate::store::{ItemStore, LocationStore};
+use askama::Template;
+use axum::extract::Multipart;
+use axum::response::{Html, Response};
+use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use std::collections::HashMap;
+
+pub struct RowError {
+    line: u64,
+    message: String,
+}
+
+pub struct PreviewRow {
+    name: String,
+    quantity: String,
+    state: String,
+    location: String,
+}
+
+#[derive(Template)]
+#[template(path = "import_result.html")]
+struct ImportResultTemplate {
+    dry_run: bool,
+    imported: usize,
+    rows: Vec<PreviewRow>,
+    errors: Vec<RowError>,
+}
+
+#[derive(Template)]
+#[template(path = "state_items_error.html")]
+struct ImportErrorTemplate {
+    error_message: String,
+}
+
+/// Imports an uploaded inventory CSV (`file`), either every row or none of them. With
+/// `dry_run` set the rows are only validated and previewed.
+#[debug_handler(state = crate::AppState)]
+pub async fn import_inventory(
+    State(pool): State<ItemStore>,
+    State(locations): State<LocationStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let mut file = None;
+    let mut dry_run = false;
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(err) => return bad_request(format!("Failed to read the upload: {err}")),
+        };
+        match field.name() {
+            Some("file") => match field.bytes().await {
+                Ok(bytes) => file = Some(bytes),
+                Err(err) => return bad_request(format!("Failed to read the upload: {err}")),
+            },
+            Some("dry_run") => dry_run = true,
+            _ => {}
+        }
+    }
+    let Some(file) = file else {
+        return bad_request("Choose a CSV file to import.".to_string());
+    };
+
+    let locations = match locations.read_locations(&actor).await {
+        Ok(locations) => locations,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read locations for import");
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to get locations.".to_string(),
+            );
+        }
+    };
+    let paths = crate::location::paths(&locations);
+    let location_ids: HashMap<String, i64> =
+        paths.iter().map(|(id, path)| (path.clone(), *id)).collect();
+
+    let (items, errors) = parse(&file, &location_ids);
+    if !errors.is_empty() {
+        let template = ImportResultTemplate {
+            dry_run,
+            imported: 0,
+            rows: Vec::new(),
+            errors,
+        };
+        return HtmlTemplate(template, StatusCode::UNPROCESSABLE_ENTITY).into_response();
+    }
+
+    let rows = items
+        .iter()
+        .map(|item| PreviewRow {
+            name: item.name.clone(),
+            quantity: item.measure().to_string(),
+            state: item.state.to_string(),
+            location: item
+                .location_id
+                .and_then(|id| paths.get(&id).cloned())
+                .unwrap_or_default(),
+        })
+        .collect();
+    let imported = items.len();
+    if !dry_run && let Err(err) = pool.create_many(items, &actor).await {
+        tracing::error!(err = %err, "failed to import items");
+        return error_response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to import items, nothing was imported.".to_string(),
+        );
+    }
+    if !dry_run {
+        live.publish(&actor, crate::item::State::all());
+    }
+
+    let template = ImportResultTemplate {
+        dry_run,
+        imported,
+        rows,
+        errors: Vec::new(),
+    };
+    HtmlTemplate(template, StatusCode::OK).into_response()
+}
+
+fn parse(file: &[u8], locations: &HashMap<String, i64>) -> (Vec<Item>, Vec<RowError>) {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(file);
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(err) => {
+            let error = RowError {
+                line: 1,
+                message: err.to_string(),
+            };
+            return (Vec::new(), vec![error]);
+        }
+    };
+
+    let mut items = Vec::new();
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                errors.push(RowError {
+                    line: err.position().map(|position| position.line()).unwrap_or(0),
+                    message: err.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record
+            .position()
+            .map(|position| position.line())
+            .unwrap_or(0);
+        let item = record
+            .deserialize::<InventoryRecord>(Some(&headers))
+            .map_err(|err| vec![err.to_string()])
+            .and_then(|record| record.into_item(locations));
+        match item {
+            Ok(item) => items.push(item),
+            Err(messages) => errors.push(RowError {
+                line,
+                message: messages.join(", "),
+            }),
+        }
+    }
+    if items.is_empty() && errors.is_empty() {
+        errors.push(RowError {
+            line: 1,
+            message: "the file contains no items".to_string(),
+        });
+    }
+    (items, errors)
+}
+
+fn bad_request(error_message: String) -> Response {
+    error_response(StatusCode::BAD_REQUEST, error_message)
+}
+
+fn error_response(status: StatusCode, error_message: String) -> Response {
+    HtmlTemplate(ImportErrorTemplate { error_message }, status).into_response()
+}
+
+struct HtmlTemplate<T>(T, StatusCode);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => (self.1, Html(html)).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use crate::actor::Actor;
+use crate::states::{StateDefinition, states};
 use askama::Template;
 use axum::{
     http::StatusCode,
@@ -6,10 +8,18 @@ use axum::{
 
 #[derive(Template)]
 #[template(path = "index.html")]
-struct IndexTemplate {}
+struct IndexTemplate {
+    username: String,
+    name_max_chars: usize,
+    states: &'static [StateDefinition],
+}
 
-pub async fn index() -> impl IntoResponse {
-    let template = IndexTemplate {};
+pub async fn index(actor: Actor) -> impl IntoResponse {
+    let template = IndexTemplate {
+        username: actor.name().to_string(),
+        name_max_chars: crate::item_validation::NAME_MAX_CHARS,
+        states: states().definitions(),
+    };
     HtmlTemplate(template)
 }
 
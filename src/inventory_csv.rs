@@ -0,0 +1,136 @@
+use crate::item::{Item, State, Unit};
+use crate::item_validation;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// One line of the inventory CSV, locations are referenced by their full path so a file
+/// can move between databases.
+#[derive(Deserialize, Serialize)]
+pub struct InventoryRecord {
+    pub name: String,
+    pub quantity: String,
+    #[serde(default)]
+    pub unit: String,
+    pub state: String,
+    #[serde(default)]
+    pub location: String,
+    #[serde(default)]
+    pub expires_on: String,
+    #[serde(default)]
+    pub par_quantity: String,
+}
+
+impl InventoryRecord {
+    pub const HEADER: [&'static str; 7] = [
+        "name",
+        "quantity",
+        "unit",
+        "state",
+        "location",
+        "expires_on",
+        "par_quantity",
+    ];
+
+    pub fn from_item(item: &Item, paths: &HashMap<i64, String>) -> Self {
+        Self {
+            name: item.name.clone(),
+            quantity: item.quantity.to_string(),
+            unit: item.unit.to_string(),
+            state: item.state.to_string(),
+            location: item
+                .location_id
+                .and_then(|id| paths.get(&id).cloned())
+                .unwrap_or_default(),
+            expires_on: item
+                .expires_on
+                .map(|date| date.to_string())
+                .unwrap_or_default(),
+            par_quantity: item
+                .par_quantity
+                .map(|par| par.to_string())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Validates every column and then the item as the forms do, returning all problems of
+    /// the line at once. `locations` maps location paths to their id.
+    pub fn into_item(self, locations: &HashMap<String, i64>) -> Result<Item, Vec<String>> {
+        let mut errors = Vec::new();
+
+        let quantity = match parse_amount(&self.quantity) {
+            Ok(Some(quantity)) => quantity,
+            Ok(None) => {
+                errors.push("quantity is empty".to_string());
+                0.0
+            }
+            Err(err) => {
+                errors.push(format!("quantity {err}"));
+                0.0
+            }
+        };
+        let unit = match self.unit.trim() {
+            "" => Unit::Piece,
+            unit => Unit::ALL
+                .iter()
+                .copied()
+                .find(|candidate| candidate.to_string() == unit)
+                .unwrap_or_else(|| {
+                    errors.push(format!("unknown unit \"{unit}\""));
+                    Unit::Piece
+                }),
+        };
+        let state = self.state.trim().parse().unwrap_or_else(|err: String| {
+            errors.push(err);
+            State::STOCK
+        });
+        let location_id = match self.location.trim() {
+            "" => None,
+            path => match locations.get(path) {
+                Some(id) => Some(*id),
+                None => {
+                    errors.push(format!("unknown location \"{path}\""));
+                    None
+                }
+            },
+        };
+        let expires_on = match self.expires_on.trim() {
+            "" => None,
+            date => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                Ok(date) => Some(date),
+                Err(_) => {
+                    errors.push(format!("expires_on \"{date}\" is not a YYYY-MM-DD date"));
+                    None
+                }
+            },
+        };
+        let par_quantity = parse_amount(&self.par_quantity).unwrap_or_else(|err| {
+            errors.push(format!("par_quantity {err}"));
+            None
+        });
+
+        let item = Item::new(0, self.name, quantity, unit, state)
+            .with_location(location_id)
+            .with_expiry(expires_on)
+            .with_par(par_quantity);
+        match item_validation::validate(item, None) {
+            Ok(item) if errors.is_empty() => Ok(item),
+            Ok(_) => Err(errors),
+            Err(invalid) => {
+                errors.extend(invalid.messages());
+                Err(errors)
+            }
+        }
+    }
+}
+
+fn parse_amount(value: &str) -> Result<Option<f64>, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    value
+        .parse::<f64>()
+        .map(Some)
+        .map_err(|_| format!("\"{value}\" is not a number"))
+}
@@ -1,48 +1,381 @@
 use std::fmt::Display;
+use std::str::FromStr;
 
-use serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(PartialEq, Deserialize, Serialize, Clone, Debug, Copy)]
-pub enum State {
-    #[serde(rename = "stock")]
-    Stock, // Means that the associated item is in stock
-    #[serde(rename = "shopping")]
-    Shopping, // Means that the associated item is in a shopping list
+use crate::states::{StateDefinition, states};
+
+/// Items expiring within this many days are flagged as expiring soon.
+pub const EXPIRING_SOON_DAYS: i64 = 3;
+
+/// Form of an item name used to match items, the store compares `lower(trim(name))`.
+pub fn normalized_name(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Looser form of a name for spotting duplicates, with the whitespace collapsed and a simple
+/// plural dropped from the last word. "Tomatoes " and "tomato" share the key.
+pub fn duplicate_key(name: &str) -> String {
+    let name = normalized_name(name);
+    let mut words: Vec<&str> = name.split_whitespace().collect();
+    let singular = words.pop().map(singular).unwrap_or_default();
+    words.push(&singular);
+    words.join(" ")
+}
+
+fn singular(word: &str) -> String {
+    if let Some(stem) = word.strip_suffix("ies")
+        && stem.len() > 1
+    {
+        return format!("{stem}y");
+    }
+    for suffix in ["sses", "shes", "ches", "xes", "oes"] {
+        if word.ends_with(suffix) {
+            return word[..word.len() - 2].to_string();
+        }
+    }
+    match word.strip_suffix('s') {
+        Some(stem) if stem.len() > 2 && !stem.ends_with('s') => stem.to_string(),
+        _ => word.to_string(),
+    }
+}
+
+/// The state an item is in, by its id in the item table. Which states exist and where items
+/// can move from each is configured, see [`crate::states`].
+#[derive(PartialEq, Eq, Hash, PartialOrd, Ord, Clone, Debug, Copy)]
+pub struct State(i64);
+
+impl State {
+    /// Means that the associated item is in stock
+    pub const STOCK: State = State(0);
+    /// Means that the associated item is in a shopping list
+    pub const SHOPPING: State = State(1);
+
+    pub fn id(self) -> i64 {
+        self.0
+    }
+
+    /// Every configured state, stock and shopping first.
+    pub fn all() -> &'static [State] {
+        states().all()
+    }
+
+    pub fn definition(self) -> Option<&'static StateDefinition> {
+        states().get(self)
+    }
+
+    /// States an item can be moved to from this one, staying put is always allowed.
+    pub fn transitions(self) -> &'static [State] {
+        self.definition()
+            .map(|definition| definition.transitions.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn can_move_to(self, state: State) -> bool {
+        self == state || self.transitions().contains(&state)
+    }
 }
 
 impl Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.definition() {
+            Some(definition) => write!(f, "{}", definition.slug),
+            // Rows in a state that was removed from the configuration
+            None => write!(f, "state-{}", self.0),
+        }
+    }
+}
+
+impl FromStr for State {
+    type Err = String;
+
+    fn from_str(slug: &str) -> Result<Self, Self::Err> {
+        states()
+            .by_slug(slug)
+            .map(|definition| definition.state)
+            .ok_or_else(|| format!("unknown state {slug:?}"))
+    }
+}
+
+impl Serialize for State {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for State {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let slug = String::deserialize(deserializer)?;
+        slug.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl From<i64> for State {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+#[derive(PartialEq, Deserialize, Serialize, Clone, Debug, Copy)]
+pub enum Unit {
+    #[serde(rename = "g")]
+    Gram,
+    #[serde(rename = "kg")]
+    Kilogram,
+    #[serde(rename = "ml")]
+    Millilitre,
+    #[serde(rename = "l")]
+    Litre,
+    #[serde(rename = "pcs")]
+    Piece,
+}
+
+#[derive(PartialEq, Clone, Debug, Copy)]
+pub enum UnitFamily {
+    Mass,
+    Volume,
+    Count,
+}
+
+impl Unit {
+    pub const ALL: &'static [Unit] = &[
+        Unit::Gram,
+        Unit::Kilogram,
+        Unit::Millilitre,
+        Unit::Litre,
+        Unit::Piece,
+    ];
+
+    pub fn family(&self) -> UnitFamily {
+        match self {
+            Unit::Gram | Unit::Kilogram => UnitFamily::Mass,
+            Unit::Millilitre | Unit::Litre => UnitFamily::Volume,
+            Unit::Piece => UnitFamily::Count,
+        }
+    }
+
+    /// Amount the consume and restock buttons move a quantity in this unit by.
+    pub fn step(&self) -> f64 {
+        match self {
+            Unit::Gram | Unit::Millilitre => 100.0,
+            Unit::Kilogram | Unit::Litre => 0.5,
+            Unit::Piece => 1.0,
+        }
+    }
+
+    // How many base units (g, ml, pcs) of the family one of this unit holds
+    fn factor(&self) -> f64 {
+        match self {
+            Unit::Kilogram | Unit::Litre => 1000.0,
+            Unit::Gram | Unit::Millilitre | Unit::Piece => 1.0,
+        }
+    }
+}
+
+impl Display for Unit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let d = match self {
-            State::Stock => "stock",
-            State::Shopping => "shopping",
+            Unit::Gram => "g",
+            Unit::Kilogram => "kg",
+            Unit::Millilitre => "ml",
+            Unit::Litre => "l",
+            Unit::Piece => "pcs",
         };
         write!(f, "{d}")
     }
 }
 
-impl From<i64> for State {
-    fn from(value: i64) -> Self {
-        match value {
-            2 => Self::Shopping,
-            _ => Self::Stock,
+impl From<String> for Unit {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "g" => Self::Gram,
+            "kg" => Self::Kilogram,
+            "ml" => Self::Millilitre,
+            "l" => Self::Litre,
+            _ => Self::Piece,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Copy)]
+pub struct Quantity {
+    pub amount: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(amount: f64, unit: Unit) -> Self {
+        Self { amount, unit }
+    }
+
+    /// Expresses the quantity in another unit, `None` when the units measure different things.
+    pub fn convert(self, unit: Unit) -> Option<Self> {
+        if self.unit.family() != unit.family() {
+            return None;
         }
+        let amount = self.amount * self.unit.factor() / unit.factor();
+        Some(Self { amount, unit })
+    }
+
+    /// Adds `other` in this quantity's unit, `None` when the units measure different things.
+    pub fn plus(self, other: Quantity) -> Option<Self> {
+        let other = other.convert(self.unit)?;
+        Some(Self::new(self.amount + other.amount, self.unit))
+    }
+
+    /// Amount in the smallest unit of the family (g, ml, pcs), for comparing across units.
+    pub fn in_base_unit(self) -> f64 {
+        self.amount * self.unit.factor()
+    }
+
+    /// Picks the unit of the family that reads best, e.g. 1500 g becomes 1.5 kg.
+    pub fn normalized(self) -> Self {
+        let unit = match self.unit.family() {
+            UnitFamily::Mass if self.amount * self.unit.factor() >= 1000.0 => Unit::Kilogram,
+            UnitFamily::Mass => Unit::Gram,
+            UnitFamily::Volume if self.amount * self.unit.factor() >= 1000.0 => Unit::Litre,
+            UnitFamily::Volume => Unit::Millilitre,
+            UnitFamily::Count => Unit::Piece,
+        };
+        self.convert(unit).unwrap_or(self)
     }
 }
 
+impl Display for Quantity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Conversions leave floating point noise behind, three decimals are plenty for a pantry
+        let amount = (self.amount * 1000.0).round() / 1000.0;
+        write!(f, "{amount} {}", self.unit)
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Copy)]
+pub enum Freshness {
+    Fresh,
+    ExpiringSoon,
+    Expired,
+}
+
+#[derive(Clone, Debug)]
 pub struct Item {
     pub id: i64,
     pub name: String,
     pub quantity: f64,
+    pub unit: Unit,
     pub state: State,
+    pub location_id: Option<i64>,
+    pub expires_on: Option<NaiveDate>,
+    pub par_quantity: Option<f64>,
+    /// Version of the stored row the item was read from, see [`Item::with_version`]
+    pub version: i64,
+}
+
+impl Display for Item {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} in {}", self.name, self.measure(), self.state)?;
+        if let Some(par) = self.par_quantity {
+            write!(f, ", min {}", Quantity::new(par, self.unit))?;
+        }
+        if let Some(expires_on) = self.expires_on {
+            write!(f, ", best before {expires_on}")?;
+        }
+        if let Some(location_id) = self.location_id {
+            write!(f, ", location #{location_id}")?;
+        }
+        Ok(())
+    }
 }
 
 impl Item {
-    pub fn new(id: i64, name: String, quantity: f64, state: State) -> Self {
+    pub fn new(id: i64, name: String, quantity: f64, unit: Unit, state: State) -> Self {
         Self {
             id,
             name,
             quantity,
+            unit,
             state,
+            location_id: None,
+            expires_on: None,
+            par_quantity: None,
+            version: 0,
+        }
+    }
+
+    /// The version an update is based on, the store rejects it when the row has moved on.
+    pub fn with_version(mut self, version: i64) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn with_location(mut self, location_id: Option<i64>) -> Self {
+        self.location_id = location_id;
+        self
+    }
+
+    pub fn with_expiry(mut self, expires_on: Option<NaiveDate>) -> Self {
+        self.expires_on = expires_on;
+        self
+    }
+
+    pub fn with_par(mut self, par_quantity: Option<f64>) -> Self {
+        self.par_quantity = par_quantity;
+        self
+    }
+
+    /// Amount missing to get a stocked item back to its par quantity.
+    pub fn shortfall(&self) -> Option<Quantity> {
+        match (self.state, self.par_quantity) {
+            (State::STOCK, Some(par)) if self.quantity < par => {
+                Some(Quantity::new(par - self.quantity, self.unit))
+            }
+            _ => None,
+        }
+    }
+
+    /// Shortfall to put on the shopping list after a change from `previous`, only when the
+    /// change dropped the quantity.
+    pub fn shortfall_since(&self, previous: &Item) -> Option<Quantity> {
+        let dropped = previous
+            .measure()
+            .convert(self.unit)
+            .is_none_or(|previous| self.quantity < previous.amount);
+        self.shortfall().filter(|_| dropped)
+    }
+
+    pub fn freshness(&self, today: NaiveDate) -> Freshness {
+        match self.expires_on {
+            Some(expires_on) if expires_on < today => Freshness::Expired,
+            Some(expires_on) if (expires_on - today).num_days() <= EXPIRING_SOON_DAYS => {
+                Freshness::ExpiringSoon
+            }
+            _ => Freshness::Fresh,
+        }
+    }
+
+    pub fn measure(&self) -> Quantity {
+        Quantity::new(self.quantity, self.unit)
+    }
+
+    /// The item with the quantities of `others` added to it, for merging duplicates. Keeps
+    /// its own details and fills the gaps from the others, the earliest best before date wins.
+    /// `None` when one of the others is measured in a different kind of unit.
+    pub fn merged_with(&self, others: &[Item]) -> Option<Item> {
+        let mut merged = self.clone();
+        for other in others {
+            merged.quantity = merged.measure().plus(other.measure())?.amount;
+            merged.location_id = merged.location_id.or(other.location_id);
+            merged.expires_on = match (merged.expires_on, other.expires_on) {
+                (Some(mine), Some(theirs)) => Some(mine.min(theirs)),
+                (mine, theirs) => mine.or(theirs),
+            };
+            if merged.par_quantity.is_none() {
+                merged.par_quantity = other
+                    .par_quantity
+                    .and_then(|par| Quantity::new(par, other.unit).convert(self.unit))
+                    .map(|par| par.amount);
+            }
         }
+        Some(merged)
     }
 }
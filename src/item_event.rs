@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use std::fmt::Display;
+
+#[derive(PartialEq, Clone, Debug, Copy)]
+pub enum EventKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl Display for EventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = match self {
+            EventKind::Created => "created",
+            EventKind::Updated => "updated",
+            EventKind::Deleted => "deleted",
+        };
+        write!(f, "{d}")
+    }
+}
+
+impl From<String> for EventKind {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "created" => Self::Created,
+            "deleted" => Self::Deleted,
+            _ => Self::Updated,
+        }
+    }
+}
+
+/// One change to an item, values are human readable snapshots of the item.
+pub struct ItemEvent {
+    pub id: i64,
+    pub item_id: i64,
+    pub kind: EventKind,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub actor: Option<String>,
+    pub created_at: NaiveDateTime,
+}
@@ -0,0 +1,54 @@
+use crate::actor::Actor;
+use crate::item_event::ItemEvent;
+use crate::store::HistoryStore;
+use askama::Template;
+use axum::response::{Html, Response};
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+#[derive(Template)]
+#[template(path = "item_history.html")]
+struct ItemHistoryTemplate {
+    events: Vec<ItemEvent>,
+}
+
+#[debug_handler]
+pub async fn item_history(
+    State(pool): State<HistoryStore>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    match pool.read_history(id, &actor).await {
+        Ok(events) => HtmlTemplate(ItemHistoryTemplate { events }).into_response(),
+        Err(err) => {
+            tracing::error!(err = %err, id, "failed to read item history");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to get item history",
+            )
+                .into_response()
+        }
+    }
+}
+
+struct HtmlTemplate<T>(T);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
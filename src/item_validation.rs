@@ -0,0 +1,130 @@
+use crate::item::{Item, State};
+use crate::purchase::Price;
+use askama::Template;
+use axum::{
+    extract::rejection::FormRejection,
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+
+/// Longest item name accepted, in characters
+pub const NAME_MAX_CHARS: usize = 100;
+
+/// A problem with one field of a submitted item form.
+pub struct FieldError {
+    /// Name of the form input, the modal marks it as invalid. Empty for the form as a whole.
+    field: &'static str,
+    label: &'static str,
+    message: String,
+}
+
+/// Shown in the add and edit modals when the item can't be saved as submitted.
+#[derive(Template)]
+#[template(path = "item_form_errors.html")]
+pub struct ItemFormErrors {
+    errors: Vec<FieldError>,
+}
+
+impl ItemFormErrors {
+    /// The form body could not be read at all, like a quantity that is not a number.
+    pub fn from_rejection(rejection: FormRejection) -> Self {
+        Self {
+            errors: vec![FieldError {
+                field: "",
+                label: "Form",
+                message: rejection.body_text(),
+            }],
+        }
+    }
+
+    /// Every problem on one line, for clients that don't render the fragment.
+    pub fn summary(&self) -> String {
+        self.messages().join("; ")
+    }
+
+    /// Every problem labelled with its field, like `Item Name: Enter a name`.
+    pub fn messages(&self) -> Vec<String> {
+        self.errors
+            .iter()
+            .map(|error| format!("{}: {}", error.label, error.message))
+            .collect()
+    }
+
+    fn add(&mut self, field: &'static str, label: &'static str, message: impl Into<String>) {
+        self.errors.push(FieldError {
+            field,
+            label,
+            message: message.into(),
+        });
+    }
+}
+
+impl IntoResponse for ItemFormErrors {
+    fn into_response(self) -> Response {
+        match self.render() {
+            Ok(html) => (StatusCode::UNPROCESSABLE_ENTITY, Html(html)).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
+
+/// Trims the name of a submitted item and checks the rest before it goes to the store.
+/// `previous` is the state the item was in for an update, `None` for a new item.
+pub fn validate(mut item: Item, previous: Option<State>) -> Result<Item, ItemFormErrors> {
+    let mut errors = ItemFormErrors { errors: Vec::new() };
+
+    item.name = item.name.trim().to_string();
+    if item.name.is_empty() {
+        errors.add("name", "Item Name", "Enter a name");
+    } else if item.name.chars().count() > NAME_MAX_CHARS {
+        errors.add(
+            "name",
+            "Item Name",
+            format!("Use at most {NAME_MAX_CHARS} characters"),
+        );
+    }
+    if !item.quantity.is_finite() || item.quantity < 0.0 {
+        errors.add("quantity", "Quantity", "Enter a number of at least 0");
+    }
+    if item
+        .par_quantity
+        .is_some_and(|par| !par.is_finite() || par < 0.0)
+    {
+        errors.add(
+            "par_quantity",
+            "Minimum Stock",
+            "Enter a number of at least 0",
+        );
+    }
+    if let Some(previous) = previous
+        && !previous.can_move_to(item.state)
+    {
+        errors.add(
+            "state",
+            "State",
+            format!("Items in {previous} can't be moved to {}", item.state),
+        );
+    }
+
+    if errors.errors.is_empty() {
+        Ok(item)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Reads the price paid entered along with a shopping item, `None` when left empty.
+pub fn price(price: Option<&str>) -> Result<Option<Price>, ItemFormErrors> {
+    match price.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(price) => price.parse().map(Some).map_err(|message: String| {
+            let mut errors = ItemFormErrors { errors: Vec::new() };
+            errors.add("price", "Price Paid", message);
+            errors
+        }),
+    }
+}
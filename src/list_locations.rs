@@ -0,0 +1,96 @@
+use crate::actor::Actor;
+use crate::error_fragment;
+use crate::location::Location;
+use crate::store::LocationStore;
+use askama::Template;
+use axum::extract::Query;
+use axum::response::{Html, Response};
+use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+
+pub struct LocationOptionTemplate {
+    pub id: i64,
+    pub path: String,
+    pub selected: bool,
+}
+
+impl LocationOptionTemplate {
+    pub fn from_locations(locations: &[Location], selected: Option<i64>) -> Vec<Self> {
+        crate::location::sorted_paths(locations)
+            .into_iter()
+            .map(|(id, path)| Self {
+                id,
+                path,
+                selected: selected == Some(id),
+            })
+            .collect()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "locations.html")]
+struct LocationsTemplate {
+    locations: Vec<LocationOptionTemplate>,
+}
+
+#[derive(Template)]
+#[template(path = "location_options.html")]
+struct LocationOptionsTemplate {
+    locations: Vec<LocationOptionTemplate>,
+}
+
+#[derive(Deserialize)]
+pub struct QueryParameters {
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    selected: Option<i64>,
+}
+
+#[debug_handler]
+pub async fn list_locations(State(pool): State<LocationStore>, actor: Actor) -> impl IntoResponse {
+    match pool.read_locations(&actor).await {
+        Ok(locations) => {
+            let locations = LocationOptionTemplate::from_locations(&locations, None);
+            HtmlTemplate(LocationsTemplate { locations }).into_response()
+        }
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read locations");
+            error_fragment::store_error(&err, "Failed to get locations")
+        }
+    }
+}
+
+#[debug_handler]
+pub async fn location_options(
+    State(pool): State<LocationStore>,
+    actor: Actor,
+    Query(query): Query<QueryParameters>,
+) -> impl IntoResponse {
+    match pool.read_locations(&actor).await {
+        Ok(locations) => {
+            let locations = LocationOptionTemplate::from_locations(&locations, query.selected);
+            HtmlTemplate(LocationOptionsTemplate { locations }).into_response()
+        }
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read locations");
+            error_fragment::store_error(&err, "Failed to get locations")
+        }
+    }
+}
+
+struct HtmlTemplate<T>(T);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
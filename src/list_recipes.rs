@@ -0,0 +1,82 @@
+use crate::actor::Actor;
+use crate::error_fragment;
+use crate::item::{Item, State as ItemState};
+use crate::recipe::Recipe;
+use crate::store::{ItemStore, RecipeStore};
+use askama::Template;
+use axum::response::{Html, Response};
+use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+
+pub struct RecipeSummaryTemplate {
+    id: i64,
+    name: String,
+    ingredients: usize,
+    missing: usize,
+}
+
+impl RecipeSummaryTemplate {
+    fn new(recipe: &Recipe, stock: &[Item]) -> Self {
+        Self {
+            id: recipe.id,
+            name: recipe.name.clone(),
+            ingredients: recipe.ingredients.len(),
+            missing: recipe
+                .ingredients
+                .iter()
+                .filter(|ingredient| ingredient.missing(stock).is_some())
+                .count(),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "recipes.html")]
+struct RecipesTemplate {
+    recipes: Vec<RecipeSummaryTemplate>,
+}
+
+#[debug_handler(state = crate::AppState)]
+pub async fn list_recipes(
+    State(pool): State<RecipeStore>,
+    State(items): State<ItemStore>,
+    actor: Actor,
+) -> impl IntoResponse {
+    let recipes = match pool.read_recipes(&actor).await {
+        Ok(recipes) => recipes,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read recipes");
+            return error_fragment::store_error(&err, "Failed to get recipes");
+        }
+    };
+    let stock = match items.read_many_from_state(ItemState::STOCK, &actor).await {
+        Ok(stock) => stock,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read stock for recipes");
+            return error_fragment::store_error(&err, "Failed to get stock");
+        }
+    };
+
+    let recipes = recipes
+        .iter()
+        .map(|recipe| RecipeSummaryTemplate::new(recipe, &stock))
+        .collect();
+    HtmlTemplate(RecipesTemplate { recipes }).into_response()
+}
+
+struct HtmlTemplate<T>(T);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
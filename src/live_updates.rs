@@ -0,0 +1,70 @@
+use crate::{actor::Actor, item::State as ItemState};
+use axum::{
+    debug_handler,
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use std::convert::Infallible;
+use tokio::sync::broadcast;
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+
+/// Changes a slow page may fall behind on before it is told to reload everything.
+const CAPACITY: usize = 64;
+
+/// Name of the event sent to a page that missed changes, every row has to reload then.
+const RELOAD_EVENT: &str = "reload";
+
+#[derive(Clone, Debug)]
+pub struct ItemChange {
+    household_id: i64,
+    state: ItemState,
+}
+
+/// Event bus the mutating handlers publish to, every open page listens on `/item/events`.
+#[derive(Clone)]
+pub struct LiveUpdates {
+    sender: broadcast::Sender<ItemChange>,
+}
+
+impl Default for LiveUpdates {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Self { sender }
+    }
+}
+
+impl LiveUpdates {
+    /// Tells the pages of the actor's household that items in `states` changed.
+    pub fn publish(&self, actor: &Actor, states: &[ItemState]) {
+        for &state in states {
+            // Sending only fails when no page is open, nobody needs to hear about it then
+            let _ = self.sender.send(ItemChange {
+                household_id: actor.household_id(),
+                state,
+            });
+        }
+    }
+
+    /// Names of the events for the actor's household, see [`live_updates`].
+    fn subscribe(&self, actor: &Actor) -> impl Stream<Item = String> + use<> {
+        let household_id = actor.household_id();
+        BroadcastStream::new(self.sender.subscribe()).filter_map(move |change| match change {
+            Ok(change) if change.household_id == household_id => Some(change.state.to_string()),
+            Ok(_) => None,
+            Err(_) => Some(RELOAD_EVENT.to_string()),
+        })
+    }
+}
+
+/// Streams a `stock` or `shopping` event per change, named after the row it affects, or a
+/// `reload` event when the page fell behind.
+#[debug_handler(state = crate::AppState)]
+pub async fn live_updates(
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let events = live
+        .subscribe(&actor)
+        .map(|name| Ok(Event::default().event(name).data("changed")));
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
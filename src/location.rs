@@ -0,0 +1,76 @@
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone)]
+pub struct Location {
+    pub id: i64,
+    pub name: String,
+    pub parent_id: Option<i64>,
+}
+
+impl Location {
+    pub fn new(id: i64, name: String, parent_id: Option<i64>) -> Self {
+        Self {
+            id,
+            name,
+            parent_id,
+        }
+    }
+}
+
+/// Full path of every location, e.g. "House › Kitchen › Fridge", keyed by location id.
+pub fn paths(locations: &[Location]) -> HashMap<i64, String> {
+    let by_id: HashMap<i64, &Location> = locations
+        .iter()
+        .map(|location| (location.id, location))
+        .collect();
+
+    locations
+        .iter()
+        .map(|location| {
+            let mut names = vec![location.name.as_str()];
+            let mut parent = location.parent_id;
+            // The depth bound protects against a corrupted tree containing a cycle
+            while let Some(current) = parent.and_then(|id| by_id.get(&id)) {
+                if names.len() > locations.len() {
+                    break;
+                }
+                names.push(current.name.as_str());
+                parent = current.parent_id;
+            }
+            names.reverse();
+            (location.id, names.join(" › "))
+        })
+        .collect()
+}
+
+/// Ids of `root` and every location nested inside it, none when `root` is not one of
+/// `locations`.
+pub fn subtree(locations: &[Location], root: i64) -> HashSet<i64> {
+    if !locations.iter().any(|location| location.id == root) {
+        return HashSet::new();
+    }
+    let mut ids = HashSet::from([root]);
+    // Each pass adds one more level, a tree is never deeper than it has locations
+    for _ in 0..locations.len() {
+        let before = ids.len();
+        for location in locations {
+            if location
+                .parent_id
+                .is_some_and(|parent| ids.contains(&parent))
+            {
+                ids.insert(location.id);
+            }
+        }
+        if ids.len() == before {
+            break;
+        }
+    }
+    ids
+}
+
+/// Location paths ordered alphabetically, which lists children right after their parent.
+pub fn sorted_paths(locations: &[Location]) -> Vec<(i64, String)> {
+    let mut paths: Vec<(i64, String)> = paths(locations).into_iter().collect();
+    paths.sort_by(|a, b| a.1.cmp(&b.1));
+    paths
+}
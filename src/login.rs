@@ -0,0 +1,104 @@
+use crate::auth::{self, SESSION_COOKIE, SessionSettings};
+use crate::store::UserStore;
+use askama::Template;
+use axum::response::{Html, Redirect, Response};
+use axum::{Form, debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use axum_extra::extract::cookie::{Cookie, CookieJar};
+use serde::Deserialize;
+
+#[derive(Template)]
+#[template(path = "login.html")]
+struct LoginTemplate {
+    username: String,
+    error_message: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LoginForm {
+    username: String,
+    password: String,
+}
+
+pub async fn get_login() -> impl IntoResponse {
+    let template = LoginTemplate {
+        username: String::new(),
+        error_message: None,
+    };
+    HtmlTemplate(template, StatusCode::OK)
+}
+
+#[debug_handler(state = crate::AppState)]
+pub async fn login(
+    State(users): State<UserStore>,
+    State(settings): State<SessionSettings>,
+    jar: CookieJar,
+    Form(form): Form<LoginForm>,
+) -> impl IntoResponse {
+    let user = match users.read_user_by_name(form.username.trim()).await {
+        Ok(user) => user,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read user");
+            let template = LoginTemplate {
+                username: form.username,
+                error_message: Some("Signing in failed, please try again.".to_string()),
+            };
+            return HtmlTemplate(template, StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    let password_hash = match &user {
+        Some(user) => &user.password_hash,
+        None => auth::dummy_password_hash(),
+    };
+    let verified = auth::verify_password(&form.password, password_hash);
+    let Some(user) = user.filter(|_| verified) else {
+        let template = LoginTemplate {
+            username: form.username,
+            error_message: Some("Unknown username or wrong password.".to_string()),
+        };
+        return HtmlTemplate(template, StatusCode::UNAUTHORIZED).into_response();
+    };
+
+    let token = auth::new_session_token();
+    let expires_at = chrono::Utc::now().naive_utc() + chrono::Duration::days(auth::SESSION_DAYS);
+    if let Err(err) = users.create_session(&token, user.id, expires_at).await {
+        tracing::error!(err = %err, "failed to create session");
+        let template = LoginTemplate {
+            username: form.username,
+            error_message: Some("Signing in failed, please try again.".to_string()),
+        };
+        return HtmlTemplate(template, StatusCode::INTERNAL_SERVER_ERROR).into_response();
+    }
+
+    (jar.add(settings.session_cookie(token)), Redirect::to("/")).into_response()
+}
+
+#[debug_handler]
+pub async fn logout(State(users): State<UserStore>, jar: CookieJar) -> impl IntoResponse {
+    if let Some(cookie) = jar.get(SESSION_COOKIE)
+        && let Err(err) = users.delete_session(cookie.value()).await
+    {
+        tracing::error!(err = %err, "failed to delete session");
+    }
+
+    let jar = jar.remove(Cookie::build(SESSION_COOKIE).path("/"));
+    (jar, Redirect::to("/login"))
+}
+
+struct HtmlTemplate<T>(T, StatusCode);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => (self.1, Html(html)).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
@@ -1,31 +1,130 @@
 use std::sync::Arc;
 
+use auth::SessionSettings;
 use axum::{
     Router,
+    extract::FromRef,
+    middleware,
     routing::{delete, get, post, put},
 };
 use config::{Config, FileFormat};
-use configuration::Configuration;
-use store::SqliteItemStore;
+use configuration::{Backend, Configuration};
+use item::Item;
+use live_updates::LiveUpdates;
+use postgres_store::PostgresItemStore;
+use states::States;
+use store::{
+    CartStore, Carts, History, HistoryStore, ItemStore, LocationStore, Locations, MealPlanStore,
+    MealPlans, PurchaseStore, Purchases, RecipeStore, Recipes, ShopStore, Shops, SqliteItemStore,
+    Store, UserStore, Users,
+};
 use tokio::net::TcpListener;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod actor;
+mod adjust_quantity;
+mod api;
+mod auth;
+mod cli;
 mod configuration;
+mod create_ingredient;
 mod create_item;
+mod create_location;
+mod create_recipe;
+mod delete_ingredient;
 mod delete_item;
+mod delete_location;
+mod delete_planned_meal;
+mod delete_recipe;
+mod duplicates;
+mod error_fragment;
+mod expiring_items;
+mod export_inventory;
+mod export_shopping;
+mod form;
+#[cfg(test)]
+mod handler_tests;
+mod import_inventory;
 mod index;
+mod inventory_csv;
 mod item;
+mod item_event;
+mod item_history;
+mod item_validation;
+mod list_locations;
+mod list_recipes;
+mod live_updates;
+mod location;
+mod login;
+mod meal_plan;
+#[cfg(test)]
+mod memory_store;
+mod page;
+mod plan_meal;
+mod postgres_store;
+mod purchase;
+mod recipe;
+mod reports;
+mod search;
+mod shop;
+mod shop_meal_plan;
+mod shopping_trip;
+mod shops;
+mod show_meal_plan;
+mod show_recipe;
 mod state_items;
+mod states;
 mod store;
+#[cfg(test)]
+mod store_conformance;
 mod update_item;
+mod update_recipe;
+mod user;
+
+#[derive(Clone, FromRef)]
+struct AppState {
+    items: ItemStore,
+    locations: LocationStore,
+    history: HistoryStore,
+    users: UserStore,
+    recipes: RecipeStore,
+    meal_plans: MealPlanStore,
+    purchases: PurchaseStore,
+    shops: ShopStore,
+    carts: CartStore,
+    live_updates: LiveUpdates,
+    sessions: SessionSettings,
+}
+
+impl AppState {
+    /// Serves every store capability from the one backend.
+    fn new<S>(store: S, sessions: SessionSettings) -> Self
+    where
+        S: Store<Item> + Locations + History + Users + Recipes + MealPlans + Purchases + Shops,
+        S: Carts,
+        S: Clone + Send + Sync + 'static,
+    {
+        Self {
+            items: Arc::new(store.clone()),
+            locations: Arc::new(store.clone()),
+            history: Arc::new(store.clone()),
+            users: Arc::new(store.clone()),
+            recipes: Arc::new(store.clone()),
+            meal_plans: Arc::new(store.clone()),
+            purchases: Arc::new(store.clone()),
+            shops: Arc::new(store.clone()),
+            carts: Arc::new(store),
+            live_updates: LiveUpdates::default(),
+            sessions,
+        }
+    }
+}
 
 #[tokio::main]
 async fn main() {
-    let path = std::env::args()
-        .skip(1)
-        .next()
-        .expect("no toml config provided");
+    let mut args = std::env::args().skip(1);
+    let path = args.next().expect("no toml config provided");
     let configuration: Configuration = Config::builder()
         // Add in `./Settings.toml`
         .add_source(config::File::new(&path, FileFormat::Toml))
@@ -51,19 +150,25 @@ async fn main() {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    let store = Arc::new(SqliteItemStore::new(&configuration.database.dsn).await);
-    let app = Router::new()
-        .route("/", get(index::index))
-        .route("/item", post(create_item::create_item))
-        .route("/item", get(state_items::state_items))
-        .route("/item", put(update_item::update_item))
-        .route("/item/{id}", delete(delete_item::delete_item))
-        .route("/item/edit-form/{id}", get(update_item::get_update_item))
-        .layer(TraceLayer::new_for_http())
-        .layer(tower_http::timeout::TimeoutLayer::new(
-            std::time::Duration::from_secs(10),
-        ))
-        .with_state(store);
+    states::init(
+        States::from_configuration(&configuration.states)
+            .unwrap_or_else(|err| panic!("invalid item states: {err}")),
+    );
+
+    let sessions = SessionSettings {
+        secure_cookies: configuration.server.secure_cookies,
+    };
+    let dsn = &configuration.database.dsn;
+    let state = match configuration.database.backend() {
+        Some(Backend::Sqlite) => AppState::new(SqliteItemStore::new(dsn).await, sessions),
+        Some(Backend::Postgres) => AppState::new(PostgresItemStore::new(dsn).await, sessions),
+        None => panic!("unsupported database dsn {dsn}, expected sqlite:// or postgres://"),
+    };
+
+    if let Some(command) = args.next() {
+        cli::run(&command, args, state.users.clone()).await;
+        return;
+    }
 
     // Create a `TcpListener` using tokio.
     let listener = TcpListener::bind(format!(
@@ -74,12 +179,102 @@ async fn main() {
     .expect("failed to create tcplistener");
 
     // Run the server with graceful shutdown
-    axum::serve(listener, app)
+    axum::serve(listener, router(state))
         .with_graceful_shutdown(shutdown_signal())
         .await
         .unwrap();
 }
 
+/// Every route of the app with its middleware, the server and the HTTP tests serve this.
+fn router(state: AppState) -> Router {
+    let protected = Router::new()
+        .route("/", get(index::index))
+        .route("/item", post(create_item::create_item))
+        .route("/item", get(state_items::state_items))
+        .route("/item", put(update_item::update_item))
+        .route("/item/{id}", delete(delete_item::delete_item))
+        .route("/item/expiring", get(expiring_items::expiring_items))
+        .route("/item/duplicates", get(duplicates::list_duplicates))
+        .route(
+            "/item/{id}/merge-duplicates",
+            post(duplicates::merge_duplicates),
+        )
+        .route("/item/events", get(live_updates::live_updates))
+        .route(
+            "/item/shopping/export",
+            get(export_shopping::export_shopping),
+        )
+        .route("/item/{id}/history", get(item_history::item_history))
+        .route("/item/{id}/consume", post(adjust_quantity::consume_item))
+        .route("/item/{id}/restock", post(adjust_quantity::restock_item))
+        .route("/item/edit-form/{id}", get(update_item::get_update_item))
+        .route("/inventory/export", get(export_inventory::export_inventory))
+        .route(
+            "/inventory/import",
+            post(import_inventory::import_inventory),
+        )
+        .route(
+            "/recipe",
+            get(list_recipes::list_recipes).post(create_recipe::create_recipe),
+        )
+        .route(
+            "/recipe/{id}",
+            get(show_recipe::show_recipe)
+                .put(update_recipe::update_recipe)
+                .delete(delete_recipe::delete_recipe),
+        )
+        .route(
+            "/recipe/{id}/ingredient",
+            post(create_ingredient::create_ingredient),
+        )
+        .route(
+            "/recipe/ingredient/{id}",
+            delete(delete_ingredient::delete_ingredient),
+        )
+        .route(
+            "/meal-plan",
+            get(show_meal_plan::show_meal_plan).post(plan_meal::plan_meal),
+        )
+        .route(
+            "/meal-plan/{id}",
+            delete(delete_planned_meal::delete_planned_meal),
+        )
+        .route("/meal-plan/shopping", post(shop_meal_plan::shop_meal_plan))
+        .route("/report", get(reports::show_reports))
+        .route("/report/prices", get(reports::price_history))
+        .route("/trip", get(shopping_trip::shopping_trip))
+        .route("/trip/item/{id}/cart", put(shopping_trip::put_in_cart))
+        .route(
+            "/trip/item/{id}/quantity",
+            put(shopping_trip::adjust_bought),
+        )
+        .route("/trip/check-out", post(shopping_trip::check_out))
+        .route("/shop", get(shops::list_shops).post(shops::create_shop))
+        .route("/shop/{id}", delete(shops::delete_shop))
+        .route("/shop/{id}/aisle", post(shops::create_aisle))
+        .route("/shop/aisle/{id}", delete(shops::delete_aisle))
+        .route("/shop/aisle/{id}/move", post(shops::move_aisle))
+        .route("/shop/{shop}/item/{item}/aisle", put(shops::place_item))
+        .route("/location", get(list_locations::list_locations))
+        .route("/location", post(create_location::create_location))
+        .route("/location/{id}", delete(delete_location::delete_location))
+        .route("/location/options", get(list_locations::location_options))
+        .nest("/api/v1", api::router())
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_actor,
+        ));
+    Router::new()
+        .route("/login", get(login::get_login).post(login::login))
+        .route("/logout", post(login::logout))
+        .merge(protected)
+        .layer(TraceLayer::new_for_http())
+        .layer(tower_http::timeout::TimeoutLayer::new(
+            std::time::Duration::from_secs(10),
+        ))
+        .with_state(state)
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         tokio::signal::ctrl_c()
@@ -0,0 +1,49 @@
+use crate::item::{Item, Quantity, normalized_name};
+use crate::recipe::{Ingredient, stock_of};
+
+pub struct PlannedMeal {
+    pub id: i64,
+    /// Days since Monday
+    pub weekday: i64,
+    pub recipe_id: i64,
+    pub recipe_name: String,
+}
+
+/// An ingredient the planned meals need more of than the stock holds.
+pub struct Need {
+    pub name: String,
+    pub missing: Quantity,
+}
+
+/// Adds up the ingredients of all planned meals per item name and subtracts the stock.
+/// Amounts are summed in the unit the name first appears with, ingredients measured in an
+/// incompatible unit (e.g. eggs by weight and by piece) are totalled separately.
+pub fn needs(ingredients: &[Ingredient], stock: &[Item]) -> Vec<Need> {
+    // (normalized name, name as first written, total)
+    let mut totals: Vec<(String, &str, Quantity)> = Vec::new();
+    for ingredient in ingredients {
+        let key = normalized_name(&ingredient.name);
+        let required = ingredient.required();
+        let total = totals.iter_mut().find_map(|(total_key, _, total)| {
+            if *total_key != key {
+                return None;
+            }
+            total.plus(required).map(|sum| (total, sum))
+        });
+        match total {
+            Some((total, sum)) => *total = sum,
+            None => totals.push((key, ingredient.name.trim(), required)),
+        }
+    }
+
+    totals
+        .into_iter()
+        .filter_map(|(_, name, total)| {
+            let missing = total.amount - stock_of(name, total.unit, stock).amount;
+            (missing > 0.0).then(|| Need {
+                name: name.to_string(),
+                missing: Quantity::new(missing, total.unit),
+            })
+        })
+        .collect()
+}
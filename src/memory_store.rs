@@ -0,0 +1,1166 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use crate::actor::Actor;
+use crate::item::{Item, Quantity, State};
+use crate::item_event::{EventKind, ItemEvent};
+use crate::location::Location;
+use crate::meal_plan::{Need, PlannedMeal};
+use crate::page::{Cursor, Page, PageRequest, SortKey};
+use crate::purchase::Purchase;
+use crate::recipe::{Ingredient, Recipe};
+use crate::search::SearchQuery;
+use crate::shop::{Aisle, Placements, Shop};
+use crate::store::{
+    Carts, CheckOut, History, Locations, MealPlans, Purchases, Recipes, Shops, Store, StoreError,
+    Users, check_aisle_order, check_merge,
+};
+use crate::user::User;
+
+/// Store keeping everything in process memory, for tests that exercise the handlers without
+/// a database. Holds to the same contract as the database stores, see `store_conformance`.
+#[derive(Clone, Default)]
+pub struct MemoryItemStore {
+    memory: Arc<Mutex<Memory>>,
+    failure: Arc<Mutex<Option<Failure>>>,
+}
+
+/// Builds the error an injected failure returns
+type Failure = fn() -> StoreError;
+
+#[derive(Default)]
+struct Memory {
+    /// One sequence for every table, ids only have to be unique per table
+    last_id: i64,
+    items: BTreeMap<i64, StoredItem>,
+    events: Vec<(i64, ItemEvent)>,
+    households: Vec<(i64, String)>,
+    users: Vec<User>,
+    sessions: HashMap<String, (i64, NaiveDateTime)>,
+    locations: Vec<(i64, Location)>,
+    recipes: Vec<(i64, Recipe)>,
+    ingredients: Vec<Ingredient>,
+    meal_plan: Vec<(i64, PlannedMeal)>,
+    purchases: Vec<(i64, Purchase)>,
+    /// Aisles kept in walking order
+    shops: Vec<(i64, Shop)>,
+    /// Item, shop and aisle
+    placements: Vec<(i64, i64, i64)>,
+}
+
+struct StoredItem {
+    item: Item,
+    household_id: i64,
+    /// Value of `last_id` when the item last changed, orders by recently updated
+    updated: i64,
+    in_cart: bool,
+}
+
+impl MemoryItemStore {
+    /// Makes the item operations fail with `failure` until [`MemoryItemStore::recover`],
+    /// the session lookup of the auth middleware keeps working.
+    pub fn fail_with(&self, failure: Failure) {
+        *self.failure.lock().unwrap() = Some(failure);
+    }
+
+    pub fn recover(&self) {
+        *self.failure.lock().unwrap() = None;
+    }
+
+    fn check(&self) -> Result<(), StoreError> {
+        match *self.failure.lock().unwrap() {
+            Some(failure) => Err(failure()),
+            None => Ok(()),
+        }
+    }
+
+    fn memory(&self) -> std::sync::MutexGuard<'_, Memory> {
+        self.memory.lock().unwrap()
+    }
+}
+
+impl Memory {
+    fn next_id(&mut self) -> i64 {
+        self.last_id += 1;
+        self.last_id
+    }
+
+    fn shop_mut(&mut self, id: i64, actor: &Actor) -> Option<&mut Shop> {
+        self.shops
+            .iter_mut()
+            .find(|(household, shop)| *household == actor.household_id() && shop.id == id)
+            .map(|(_, shop)| shop)
+    }
+
+    fn item(&self, id: i64, actor: &Actor) -> Option<&Item> {
+        self.items
+            .get(&id)
+            .filter(|stored| stored.household_id == actor.household_id())
+            .map(|stored| &stored.item)
+    }
+
+    fn items_in(&self, state: State, actor: &Actor) -> impl Iterator<Item = &Item> {
+        self.items
+            .values()
+            .filter(move |stored| {
+                stored.household_id == actor.household_id() && stored.item.state == state
+            })
+            .map(|stored| &stored.item)
+    }
+
+    /// Stands in for the guard keeping items and nested locations in the household's own
+    /// locations.
+    fn check_location(&self, location_id: Option<i64>, actor: &Actor) -> Result<(), StoreError> {
+        match location_id {
+            Some(id)
+                if !self.locations.iter().any(|(household, location)| {
+                    *household == actor.household_id() && location.id == id
+                }) =>
+            {
+                Err(StoreError::NotFound)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn save_item(&mut self, record: &Item, actor: &Actor) -> Result<(), StoreError> {
+        let Some(previous) = self.item(record.id, actor).cloned() else {
+            return Err(StoreError::NotFound);
+        };
+        if previous.version != record.version {
+            return Err(StoreError::Conflict);
+        }
+        self.check_location(record.location_id, actor)?;
+        self.replace_item(record, actor);
+        self.restock_shortfall(&previous, record, actor);
+
+        Ok(())
+    }
+
+    fn insert_item(&mut self, record: &Item, actor: &Actor) -> i64 {
+        let id = self.next_id();
+        let item = Item {
+            id,
+            version: 1,
+            ..record.clone()
+        };
+        self.items.insert(
+            id,
+            StoredItem {
+                item,
+                household_id: actor.household_id(),
+                updated: id,
+                in_cart: false,
+            },
+        );
+        self.record_event(id, EventKind::Created, None, Some(record), actor);
+        id
+    }
+
+    /// Replaces the stored item and bumps its version. An item moved to another state leaves
+    /// the cart.
+    fn replace_item(&mut self, record: &Item, actor: &Actor) {
+        let updated = self.next_id();
+        let stored = self.items.get_mut(&record.id).expect("item exists");
+        stored.in_cart &= stored.item.state == record.state;
+        let item = Item {
+            version: stored.item.version + 1,
+            ..record.clone()
+        };
+        let previous = std::mem::replace(&mut stored.item, item);
+        stored.updated = updated;
+        self.record_event(
+            record.id,
+            EventKind::Updated,
+            Some(&previous),
+            Some(record),
+            actor,
+        );
+    }
+
+    /// Deletes `other` after it was merged into `into`, its recipes and aisles use `into`
+    /// from now on.
+    fn fold(&mut self, into: i64, other: &Item, actor: &Actor) {
+        for ingredient in self
+            .ingredients
+            .iter_mut()
+            .filter(|ingredient| ingredient.item_id == other.id)
+        {
+            ingredient.item_id = into;
+        }
+        // The item keeps its own aisle in a shop where both were placed
+        let taken: Vec<(i64, i64, i64)> = self
+            .placements
+            .iter()
+            .filter(|(item, shop, _)| {
+                *item == other.id
+                    && !self
+                        .placements
+                        .iter()
+                        .any(|(placed, placed_shop, _)| *placed == into && placed_shop == shop)
+            })
+            .map(|(_, shop, aisle)| (into, *shop, *aisle))
+            .collect();
+        self.items.remove(&other.id);
+        self.placements.retain(|(item, _, _)| *item != other.id);
+        self.placements.extend(taken);
+        self.record_event(other.id, EventKind::Deleted, Some(other), None, actor);
+    }
+
+    fn record_event(
+        &mut self,
+        item_id: i64,
+        kind: EventKind,
+        old: Option<&Item>,
+        new: Option<&Item>,
+        actor: &Actor,
+    ) {
+        let event = ItemEvent {
+            id: self.next_id(),
+            item_id,
+            kind,
+            old_value: old.map(Item::to_string),
+            new_value: new.map(Item::to_string),
+            actor: Some(actor.name().to_string()),
+            created_at: Utc::now().naive_utc(),
+        };
+        self.events.push((actor.household_id(), event));
+    }
+
+    /// Puts the shortfall on the shopping list when an update dropped the quantity below par.
+    fn restock_shortfall(&mut self, previous: &Item, record: &Item, actor: &Actor) {
+        if let Some(shortfall) = record.shortfall_since(previous) {
+            self.add_to_shopping(
+                &record.name,
+                record.location_id,
+                shortfall,
+                Some(record.id),
+                actor,
+            );
+        }
+    }
+
+    /// Makes sure the shopping list holds at least `shortfall` of `name`, topping up an entry
+    /// of that name already on the list instead of adding a second one. A new entry is placed
+    /// in the aisles of the stock item `aisles_of`.
+    fn add_to_shopping(
+        &mut self,
+        name: &str,
+        location_id: Option<i64>,
+        shortfall: Quantity,
+        aisles_of: Option<i64>,
+        actor: &Actor,
+    ) {
+        let normalized = crate::item::normalized_name(name);
+        let existing: Vec<Item> = self
+            .items_in(State::SHOPPING, actor)
+            .filter(|item| crate::item::normalized_name(&item.name) == normalized)
+            .cloned()
+            .collect();
+        for previous in existing {
+            let Some(needed) = shortfall.convert(previous.unit) else {
+                continue;
+            };
+            if previous.quantity < needed.amount {
+                let record = Item {
+                    quantity: needed.amount,
+                    ..previous
+                };
+                self.replace_item(&record, actor);
+            }
+            return;
+        }
+
+        let record = Item::new(
+            0,
+            name.to_string(),
+            shortfall.amount,
+            shortfall.unit,
+            State::SHOPPING,
+        )
+        .with_location(location_id);
+        let id = self.insert_item(&record, actor);
+        let placed: Vec<(i64, i64, i64)> = self
+            .placements
+            .iter()
+            .filter(|(item, _, _)| Some(*item) == aisles_of)
+            .map(|(_, shop, aisle)| (id, *shop, *aisle))
+            .collect();
+        self.placements.extend(placed);
+    }
+
+    fn adjust_quantity(&mut self, id: i64, delta: f64, actor: &Actor) -> Result<(), StoreError> {
+        let Some(previous) = self.item(id, actor).cloned() else {
+            return Err(StoreError::NotFound);
+        };
+        let record = Item {
+            quantity: (previous.quantity + delta).max(0.0),
+            ..previous.clone()
+        };
+        self.replace_item(&record, actor);
+        self.restock_shortfall(&previous, &record, actor);
+        Ok(())
+    }
+
+    /// Sort values of the item for `sort`, the same the database stores page by.
+    fn sort_values(&self, stored: &StoredItem, sort: SortKey) -> (String, f64) {
+        let item = &stored.item;
+        match sort {
+            SortKey::Name => (item.name.to_lowercase(), 0.0),
+            SortKey::Quantity => (String::new(), item.measure().in_base_unit()),
+            SortKey::Updated => (String::new(), -(stored.updated as f64)),
+            SortKey::Expiry => {
+                let expires_on = item.expires_on.unwrap_or(NaiveDate::MAX);
+                let days = (expires_on - NaiveDate::default()).num_days();
+                (String::new(), days as f64)
+            }
+        }
+    }
+
+    fn ingredients_of(&self, recipe_id: i64) -> Vec<Ingredient> {
+        self.ingredients
+            .iter()
+            .filter(|ingredient| ingredient.recipe_id == recipe_id)
+            .map(|ingredient| Ingredient {
+                name: self.items.get(&ingredient.item_id).map_or_else(
+                    || ingredient.name.clone(),
+                    |stored| stored.item.name.clone(),
+                ),
+                ..ingredient.clone()
+            })
+            .collect()
+    }
+
+    fn recipe(&self, id: i64, actor: &Actor) -> Option<&Recipe> {
+        self.recipes
+            .iter()
+            .find(|(household, recipe)| *household == actor.household_id() && recipe.id == id)
+            .map(|(_, recipe)| recipe)
+    }
+}
+
+#[async_trait]
+impl Store<Item> for MemoryItemStore {
+    async fn create(&self, record: Item, actor: &Actor) -> Result<i64, StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        memory.check_location(record.location_id, actor)?;
+        Ok(memory.insert_item(&record, actor))
+    }
+
+    async fn create_many(&self, records: Vec<Item>, actor: &Actor) -> Result<Vec<i64>, StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        for record in &records {
+            memory.check_location(record.location_id, actor)?;
+        }
+        Ok(records
+            .iter()
+            .map(|record| memory.insert_item(record, actor))
+            .collect())
+    }
+
+    async fn delete(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        let Some(previous) = memory.item(id, actor).cloned() else {
+            return Err(StoreError::NotFound);
+        };
+        memory.items.remove(&id);
+        // Recipes keep the ingredient under the name the item had last
+        for ingredient in memory
+            .ingredients
+            .iter_mut()
+            .filter(|ingredient| ingredient.item_id == id)
+        {
+            ingredient.name = previous.name.clone();
+        }
+        memory.placements.retain(|(item, _, _)| *item != id);
+        memory.record_event(id, EventKind::Deleted, Some(&previous), None, actor);
+
+        Ok(())
+    }
+
+    async fn update(&self, record: Item, actor: &Actor) -> Result<(), StoreError> {
+        self.check()?;
+        self.memory().save_item(&record, actor)
+    }
+
+    async fn consume(&self, id: i64, amount: f64, actor: &Actor) -> Result<(), StoreError> {
+        self.check()?;
+        self.memory().adjust_quantity(id, -amount, actor)
+    }
+
+    async fn restock(&self, id: i64, amount: f64, actor: &Actor) -> Result<(), StoreError> {
+        self.check()?;
+        self.memory().adjust_quantity(id, amount, actor)
+    }
+
+    async fn merge(&self, into: i64, from: &[i64], actor: &Actor) -> Result<(), StoreError> {
+        self.check()?;
+        check_merge(into, from)?;
+        let mut memory = self.memory();
+        let target = memory
+            .item(into, actor)
+            .cloned()
+            .ok_or(StoreError::NotFound)?;
+        let others = from
+            .iter()
+            .map(|id| memory.item(*id, actor).cloned().ok_or(StoreError::NotFound))
+            .collect::<Result<Vec<_>, _>>()?;
+        let merged = target.merged_with(&others).ok_or_else(|| {
+            StoreError::Validation("the items are measured in different kinds of units".to_string())
+        })?;
+
+        memory.replace_item(&merged, actor);
+        for other in &others {
+            memory.fold(into, other, actor);
+        }
+
+        Ok(())
+    }
+
+    async fn read(&self, id: i64, actor: &Actor) -> Result<Item, StoreError> {
+        self.check()?;
+        self.memory()
+            .item(id, actor)
+            .cloned()
+            .ok_or(StoreError::NotFound)
+    }
+
+    async fn read_many_from_state(
+        &self,
+        state: State,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        self.check()?;
+        Ok(self.memory().items_in(state, actor).cloned().collect())
+    }
+
+    async fn read_many_from_state_in_location(
+        &self,
+        state: State,
+        location: i64,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        self.check()?;
+        let locations = self.read_locations(actor).await?;
+        let scope = crate::location::subtree(&locations, location);
+        Ok(self
+            .memory()
+            .items_in(state, actor)
+            .filter(|item| item.location_id.is_some_and(|id| scope.contains(&id)))
+            .cloned()
+            .collect())
+    }
+
+    async fn read_page(
+        &self,
+        state: State,
+        request: &PageRequest,
+        actor: &Actor,
+    ) -> Result<Page<Item>, StoreError> {
+        self.check()?;
+        let scope = match request.location {
+            Some(location) => Some(crate::location::subtree(
+                &self.read_locations(actor).await?,
+                location,
+            )),
+            None => None,
+        };
+        let memory = self.memory();
+        let mut rows: Vec<(String, f64, &Item)> = memory
+            .items
+            .values()
+            .filter(|stored| {
+                stored.household_id == actor.household_id() && stored.item.state == state
+            })
+            .filter(|stored| {
+                scope.as_ref().is_none_or(|scope| {
+                    stored
+                        .item
+                        .location_id
+                        .is_some_and(|id| scope.contains(&id))
+                })
+            })
+            .map(|stored| {
+                let (text, number) = memory.sort_values(stored, request.sort);
+                (text, number, &stored.item)
+            })
+            .filter(|(text, number, item)| match &request.after {
+                Some(after) => {
+                    (text.as_str(), *number, item.id)
+                        > (after.text.as_str(), after.number, after.id)
+                }
+                None => true,
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            (a.0.as_str(), a.1, a.2.id)
+                .partial_cmp(&(b.0.as_str(), b.1, b.2.id))
+                .expect("sort values are not NaN")
+        });
+
+        let limit = usize::try_from(request.limit).unwrap_or(usize::MAX);
+        let has_more = rows.len() > limit;
+        rows.truncate(limit);
+        let next = rows
+            .last()
+            .filter(|_| has_more)
+            .map(|(text, number, item)| Cursor {
+                text: text.clone(),
+                number: *number,
+                id: item.id,
+            });
+        let records = rows.into_iter().map(|(_, _, item)| item.clone()).collect();
+
+        Ok(Page { records, next })
+    }
+
+    async fn read_expiring_until(
+        &self,
+        until: NaiveDate,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        self.check()?;
+        let mut records: Vec<Item> = self
+            .memory()
+            .items_in(State::STOCK, actor)
+            .filter(|item| {
+                item.expires_on
+                    .is_some_and(|expires_on| expires_on <= until)
+            })
+            .cloned()
+            .collect();
+        records.sort_by(|a, b| (a.expires_on, &a.name).cmp(&(b.expires_on, &b.name)));
+
+        Ok(records)
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        state: State,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        let query = SearchQuery::new(query);
+        let candidates = self.read_many_from_state(state, actor).await?;
+        if query.is_empty() {
+            return Ok(candidates);
+        }
+
+        let mut scored: Vec<(f64, Item)> = candidates
+            .into_iter()
+            .filter_map(|item| query.score(&item.name).map(|score| (score, item)))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        Ok(scored.into_iter().map(|(_, item)| item).collect())
+    }
+}
+
+#[async_trait]
+impl Locations for MemoryItemStore {
+    async fn create_location(&self, location: Location, actor: &Actor) -> Result<i64, StoreError> {
+        let mut memory = self.memory();
+        memory.check_location(location.parent_id, actor)?;
+        let id = memory.next_id();
+        memory.locations.push((
+            actor.household_id(),
+            Location::new(id, location.name, location.parent_id),
+        ));
+
+        Ok(id)
+    }
+
+    async fn delete_location(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let locations = self.read_locations(actor).await?;
+        if !locations.iter().any(|location| location.id == id) {
+            return Err(StoreError::NotFound);
+        }
+        // Nested locations go with their parent, items stay without a location
+        let removed: HashSet<i64> = crate::location::subtree(&locations, id);
+        let mut memory = self.memory();
+        memory
+            .locations
+            .retain(|(_, location)| !removed.contains(&location.id));
+        for stored in memory.items.values_mut() {
+            if stored
+                .item
+                .location_id
+                .is_some_and(|id| removed.contains(&id))
+            {
+                stored.item.location_id = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn read_locations(&self, actor: &Actor) -> Result<Vec<Location>, StoreError> {
+        let mut records: Vec<Location> = self
+            .memory()
+            .locations
+            .iter()
+            .filter(|(household, _)| *household == actor.household_id())
+            .map(|(_, location)| location.clone())
+            .collect();
+        records.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(records)
+    }
+}
+
+#[async_trait]
+impl History for MemoryItemStore {
+    async fn read_history(
+        &self,
+        item_id: i64,
+        actor: &Actor,
+    ) -> Result<Vec<ItemEvent>, StoreError> {
+        Ok(self
+            .memory()
+            .events
+            .iter()
+            .rev()
+            .filter(|(household, event)| {
+                *household == actor.household_id() && event.item_id == item_id
+            })
+            .map(|(_, event)| ItemEvent {
+                old_value: event.old_value.clone(),
+                new_value: event.new_value.clone(),
+                actor: event.actor.clone(),
+                ..*event
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Users for MemoryItemStore {
+    async fn create_user(
+        &self,
+        username: &str,
+        password_hash: &str,
+        household: &str,
+    ) -> Result<i64, StoreError> {
+        let mut memory = self.memory();
+        if memory.users.iter().any(|user| user.username == username) {
+            return Err(StoreError::Conflict);
+        }
+        let household_id = match memory.households.iter().find(|(_, name)| name == household) {
+            Some((id, _)) => *id,
+            None => {
+                let id = memory.next_id();
+                memory.households.push((id, household.to_string()));
+                id
+            }
+        };
+        let id = memory.next_id();
+        memory.users.push(User {
+            id,
+            username: username.to_string(),
+            password_hash: password_hash.to_string(),
+            household_id,
+        });
+
+        Ok(id)
+    }
+
+    async fn read_user_by_name(&self, username: &str) -> Result<Option<User>, StoreError> {
+        Ok(self
+            .memory()
+            .users
+            .iter()
+            .find(|user| user.username == username)
+            .cloned())
+    }
+
+    async fn create_session(
+        &self,
+        token: &str,
+        user_id: i64,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), StoreError> {
+        let now = Utc::now().naive_utc();
+        let mut memory = self.memory();
+        memory
+            .sessions
+            .retain(|_, (_, expires_at)| *expires_at > now);
+        memory
+            .sessions
+            .insert(token.to_string(), (user_id, expires_at));
+
+        Ok(())
+    }
+
+    async fn read_session_user(&self, token: &str) -> Result<Option<User>, StoreError> {
+        let now = Utc::now().naive_utc();
+        let memory = self.memory();
+        Ok(memory
+            .sessions
+            .get(token)
+            .filter(|(_, expires_at)| *expires_at > now)
+            .and_then(|(user_id, _)| memory.users.iter().find(|user| user.id == *user_id))
+            .cloned())
+    }
+
+    async fn delete_session(&self, token: &str) -> Result<(), StoreError> {
+        self.memory().sessions.remove(token);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Recipes for MemoryItemStore {
+    async fn create_recipe(&self, name: &str, actor: &Actor) -> Result<i64, StoreError> {
+        let mut memory = self.memory();
+        let id = memory.next_id();
+        memory
+            .recipes
+            .push((actor.household_id(), Recipe::new(id, name.to_string())));
+
+        Ok(id)
+    }
+
+    async fn update_recipe(&self, id: i64, name: &str, actor: &Actor) -> Result<(), StoreError> {
+        let mut memory = self.memory();
+        let (_, recipe) = memory
+            .recipes
+            .iter_mut()
+            .find(|(household, recipe)| *household == actor.household_id() && recipe.id == id)
+            .ok_or(StoreError::NotFound)?;
+        recipe.name = name.to_string();
+
+        Ok(())
+    }
+
+    async fn delete_recipe(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let mut memory = self.memory();
+        if memory.recipe(id, actor).is_none() {
+            return Err(StoreError::NotFound);
+        }
+        memory.recipes.retain(|(_, recipe)| recipe.id != id);
+        memory
+            .ingredients
+            .retain(|ingredient| ingredient.recipe_id != id);
+        memory.meal_plan.retain(|(_, meal)| meal.recipe_id != id);
+
+        Ok(())
+    }
+
+    async fn read_recipe(&self, id: i64, actor: &Actor) -> Result<Recipe, StoreError> {
+        let memory = self.memory();
+        let recipe = memory.recipe(id, actor).ok_or(StoreError::NotFound)?;
+        let mut recipe = Recipe::new(recipe.id, recipe.name.clone());
+        recipe.ingredients = memory.ingredients_of(id);
+
+        Ok(recipe)
+    }
+
+    async fn read_recipes(&self, actor: &Actor) -> Result<Vec<Recipe>, StoreError> {
+        let memory = self.memory();
+        let mut recipes: Vec<Recipe> = memory
+            .recipes
+            .iter()
+            .filter(|(household, _)| *household == actor.household_id())
+            .map(|(_, recipe)| {
+                let mut copy = Recipe::new(recipe.id, recipe.name.clone());
+                copy.ingredients = memory.ingredients_of(recipe.id);
+                copy
+            })
+            .collect();
+        recipes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(recipes)
+    }
+
+    async fn create_ingredient(
+        &self,
+        ingredient: Ingredient,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        let mut memory = self.memory();
+        let Some(name) = memory
+            .item(ingredient.item_id, actor)
+            .map(|item| item.name.clone())
+            .filter(|_| memory.recipe(ingredient.recipe_id, actor).is_some())
+        else {
+            return Err(StoreError::NotFound);
+        };
+        let id = memory.next_id();
+        memory.ingredients.push(Ingredient {
+            id,
+            name,
+            ..ingredient
+        });
+
+        Ok(id)
+    }
+
+    async fn delete_ingredient(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let mut memory = self.memory();
+        let owned: HashSet<i64> = memory
+            .recipes
+            .iter()
+            .filter(|(household, _)| *household == actor.household_id())
+            .map(|(_, recipe)| recipe.id)
+            .collect();
+        let before = memory.ingredients.len();
+        memory
+            .ingredients
+            .retain(|ingredient| ingredient.id != id || !owned.contains(&ingredient.recipe_id));
+        if memory.ingredients.len() == before {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MealPlans for MemoryItemStore {
+    async fn read_meal_plan(&self, actor: &Actor) -> Result<Vec<PlannedMeal>, StoreError> {
+        let memory = self.memory();
+        let mut records: Vec<PlannedMeal> = memory
+            .meal_plan
+            .iter()
+            .filter(|(household, _)| *household == actor.household_id())
+            .map(|(_, meal)| PlannedMeal {
+                recipe_name: memory
+                    .recipe(meal.recipe_id, actor)
+                    .map_or_else(String::new, |recipe| recipe.name.clone()),
+                ..*meal
+            })
+            .collect();
+        records.sort_by_key(|meal| (meal.weekday, meal.id));
+
+        Ok(records)
+    }
+
+    async fn plan_meal(
+        &self,
+        weekday: i64,
+        recipe_id: i64,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        let mut memory = self.memory();
+        if memory.recipe(recipe_id, actor).is_none() {
+            return Err(StoreError::NotFound);
+        }
+        let id = memory.next_id();
+        memory.meal_plan.push((
+            actor.household_id(),
+            PlannedMeal {
+                id,
+                weekday,
+                recipe_id,
+                recipe_name: String::new(),
+            },
+        ));
+
+        Ok(id)
+    }
+
+    async fn delete_planned_meal(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let mut memory = self.memory();
+        let before = memory.meal_plan.len();
+        memory
+            .meal_plan
+            .retain(|(household, meal)| *household != actor.household_id() || meal.id != id);
+        if memory.meal_plan.len() == before {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn shop_for_meal_plan(&self, actor: &Actor) -> Result<Vec<Need>, StoreError> {
+        let mut memory = self.memory();
+        // A recipe planned twice brings its ingredients twice
+        let ingredients: Vec<Ingredient> = memory
+            .meal_plan
+            .iter()
+            .filter(|(household, _)| *household == actor.household_id())
+            .flat_map(|(_, meal)| memory.ingredients_of(meal.recipe_id))
+            .collect();
+        let stock: Vec<Item> = memory.items_in(State::STOCK, actor).cloned().collect();
+
+        let needs = crate::meal_plan::needs(&ingredients, &stock);
+        for need in &needs {
+            memory.add_to_shopping(&need.name, None, need.missing, None, actor);
+        }
+
+        Ok(needs)
+    }
+}
+
+#[async_trait]
+impl Purchases for MemoryItemStore {
+    async fn record_purchase(&self, purchase: Purchase, actor: &Actor) -> Result<i64, StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        if memory.item(purchase.item_id, actor).is_none() {
+            return Err(StoreError::NotFound);
+        }
+        let id = memory.next_id();
+        memory
+            .purchases
+            .push((actor.household_id(), Purchase { id, ..purchase }));
+
+        Ok(id)
+    }
+
+    async fn buy(
+        &self,
+        record: Item,
+        purchase: Purchase,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        // Checked before the item changes, the two are written together or not at all
+        if memory.item(purchase.item_id, actor).is_none() {
+            return Err(StoreError::NotFound);
+        }
+        memory.save_item(&record, actor)?;
+        let id = memory.next_id();
+        memory
+            .purchases
+            .push((actor.household_id(), Purchase { id, ..purchase }));
+
+        Ok(id)
+    }
+
+    async fn read_purchases(&self, actor: &Actor) -> Result<Vec<Purchase>, StoreError> {
+        self.check()?;
+        let mut records: Vec<Purchase> = self
+            .memory()
+            .purchases
+            .iter()
+            .filter(|(household, _)| *household == actor.household_id())
+            .map(|(_, purchase)| purchase.clone())
+            .collect();
+        records.sort_by_key(|purchase| std::cmp::Reverse((purchase.purchased_on, purchase.id)));
+
+        Ok(records)
+    }
+}
+
+#[async_trait]
+impl Carts for MemoryItemStore {
+    async fn set_in_cart(
+        &self,
+        item_id: i64,
+        in_cart: bool,
+        actor: &Actor,
+    ) -> Result<(), StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        let stored = memory
+            .items
+            .get_mut(&item_id)
+            .filter(|stored| {
+                stored.household_id == actor.household_id() && stored.item.state == State::SHOPPING
+            })
+            .ok_or(StoreError::NotFound)?;
+        stored.in_cart = in_cart;
+
+        Ok(())
+    }
+
+    async fn read_cart(&self, actor: &Actor) -> Result<Vec<i64>, StoreError> {
+        self.check()?;
+        Ok(self
+            .memory()
+            .items
+            .values()
+            .filter(|stored| {
+                stored.household_id == actor.household_id()
+                    && stored.item.state == State::SHOPPING
+                    && stored.in_cart
+            })
+            .map(|stored| stored.item.id)
+            .collect())
+    }
+
+    async fn check_out(&self, actor: &Actor) -> Result<usize, StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        let cart: Vec<Item> = memory
+            .items
+            .values()
+            .filter(|stored| {
+                stored.household_id == actor.household_id()
+                    && stored.item.state == State::SHOPPING
+                    && stored.in_cart
+            })
+            .map(|stored| stored.item.clone())
+            .collect();
+        let stock: Vec<Item> = memory.items_in(State::STOCK, actor).cloned().collect();
+        let count = cart.len();
+        let check_out = CheckOut::plan(cart, &stock);
+
+        for (into, items, merged) in &check_out.merges {
+            memory.replace_item(merged, actor);
+            for item in items {
+                memory.fold(into.id, item, actor);
+            }
+        }
+        for (_, moved) in &check_out.moves {
+            memory.replace_item(moved, actor);
+        }
+
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl Shops for MemoryItemStore {
+    async fn create_shop(&self, name: &str, actor: &Actor) -> Result<i64, StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        let id = memory.next_id();
+        memory.shops.push((
+            actor.household_id(),
+            Shop {
+                id,
+                name: name.to_string(),
+                aisles: Vec::new(),
+            },
+        ));
+
+        Ok(id)
+    }
+
+    async fn delete_shop(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        if memory.shop_mut(id, actor).is_none() {
+            return Err(StoreError::NotFound);
+        }
+        memory.shops.retain(|(_, shop)| shop.id != id);
+        memory.placements.retain(|(_, shop, _)| *shop != id);
+
+        Ok(())
+    }
+
+    async fn read_shops(&self, actor: &Actor) -> Result<Vec<Shop>, StoreError> {
+        self.check()?;
+        let mut records: Vec<Shop> = self
+            .memory()
+            .shops
+            .iter()
+            .filter(|(household, _)| *household == actor.household_id())
+            .map(|(_, shop)| shop.clone())
+            .collect();
+        records.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+
+        Ok(records)
+    }
+
+    async fn create_aisle(
+        &self,
+        shop_id: i64,
+        name: &str,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        let id = memory.next_id();
+        let shop = memory
+            .shop_mut(shop_id, actor)
+            .ok_or(StoreError::NotFound)?;
+        shop.aisles.push(Aisle {
+            id,
+            shop_id,
+            name: name.to_string(),
+        });
+
+        Ok(id)
+    }
+
+    async fn delete_aisle(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        let shop = memory
+            .shops
+            .iter_mut()
+            .filter(|(household, _)| *household == actor.household_id())
+            .map(|(_, shop)| shop)
+            .find(|shop| shop.aisles.iter().any(|aisle| aisle.id == id))
+            .ok_or(StoreError::NotFound)?;
+        shop.aisles.retain(|aisle| aisle.id != id);
+        memory.placements.retain(|(_, _, aisle)| *aisle != id);
+
+        Ok(())
+    }
+
+    async fn reorder_aisles(
+        &self,
+        shop_id: i64,
+        order: &[i64],
+        actor: &Actor,
+    ) -> Result<(), StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        let shop = memory
+            .shop_mut(shop_id, actor)
+            .ok_or(StoreError::NotFound)?;
+        check_aisle_order(&shop.aisles, order)?;
+        shop.aisles
+            .sort_by_key(|aisle| order.iter().position(|id| *id == aisle.id));
+
+        Ok(())
+    }
+
+    async fn place_item(
+        &self,
+        item_id: i64,
+        shop_id: i64,
+        aisle_id: Option<i64>,
+        actor: &Actor,
+    ) -> Result<(), StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        let Some(shop) = memory.shop_mut(shop_id, actor) else {
+            return match aisle_id {
+                Some(_) => Err(StoreError::NotFound),
+                None => Ok(()),
+            };
+        };
+        let Some(aisle_id) = aisle_id else {
+            memory
+                .placements
+                .retain(|(item, shop, _)| (*item, *shop) != (item_id, shop_id));
+            return Ok(());
+        };
+        let in_shop = shop.aisles.iter().any(|aisle| aisle.id == aisle_id);
+        if !in_shop || memory.item(item_id, actor).is_none() {
+            return Err(StoreError::NotFound);
+        }
+        memory
+            .placements
+            .retain(|(item, shop, _)| (*item, *shop) != (item_id, shop_id));
+        memory.placements.push((item_id, shop_id, aisle_id));
+
+        Ok(())
+    }
+
+    async fn read_placements(&self, shop_id: i64, actor: &Actor) -> Result<Placements, StoreError> {
+        self.check()?;
+        let mut memory = self.memory();
+        if memory.shop_mut(shop_id, actor).is_none() {
+            return Ok(Placements::new());
+        }
+
+        Ok(memory
+            .placements
+            .iter()
+            .filter(|(_, shop, _)| *shop == shop_id)
+            .map(|(item, _, aisle)| (*item, *aisle))
+            .collect())
+    }
+}
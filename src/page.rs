@@ -0,0 +1,100 @@
+use serde::Deserialize;
+use std::{fmt::Display, str::FromStr};
+
+/// Items shown per page of a state list.
+pub const PAGE_SIZE: i64 = 25;
+
+#[derive(PartialEq, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    #[default]
+    Name,
+    /// Smallest quantity first
+    Quantity,
+    /// Most recently changed first
+    Updated,
+    /// Soonest best before date first, items without one last
+    Expiry,
+}
+
+impl SortKey {
+    pub const ALL: &'static [SortKey] = &[
+        SortKey::Name,
+        SortKey::Quantity,
+        SortKey::Updated,
+        SortKey::Expiry,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortKey::Name => "Name",
+            SortKey::Quantity => "Quantity",
+            SortKey::Updated => "Recently updated",
+            SortKey::Expiry => "Best before",
+        }
+    }
+}
+
+impl Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = match self {
+            SortKey::Name => "name",
+            SortKey::Quantity => "quantity",
+            SortKey::Updated => "updated",
+            SortKey::Expiry => "expiry",
+        };
+        write!(f, "{d}")
+    }
+}
+
+/// Position after the last item of a page. The store orders by a text key, then a number
+/// key, then the id, whichever key the sort does not use is constant.
+#[derive(PartialEq, Clone, Debug)]
+pub struct Cursor {
+    pub text: String,
+    pub number: f64,
+    pub id: i64,
+}
+
+impl Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The text goes last, it may contain the separator
+        write!(f, "{}:{}:{}", self.id, self.number, self.text)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':');
+        let (Some(id), Some(number), Some(text)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(format!("malformed cursor \"{s}\""));
+        };
+        Ok(Self {
+            id: id
+                .parse()
+                .map_err(|_| format!("malformed cursor id \"{id}\""))?,
+            number: number
+                .parse()
+                .map_err(|_| format!("malformed cursor number \"{number}\""))?,
+            text: text.to_string(),
+        })
+    }
+}
+
+pub struct PageRequest {
+    pub sort: SortKey,
+    /// Only records stored in this location or nested inside it
+    pub location: Option<i64>,
+    pub after: Option<Cursor>,
+    /// Most records on the page, usually [`PAGE_SIZE`]
+    pub limit: i64,
+}
+
+pub struct Page<T> {
+    pub records: Vec<T>,
+    /// Where the next page starts, `None` on the last page
+    pub next: Option<Cursor>,
+}
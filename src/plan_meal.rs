@@ -0,0 +1,28 @@
+use crate::{actor::Actor, error_fragment, store::MealPlanStore};
+use axum::{Form, debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct PlanMealForm {
+    /// Days since Monday
+    weekday: i64,
+    recipe: i64,
+}
+
+#[debug_handler]
+pub async fn plan_meal(
+    State(pool): State<MealPlanStore>,
+    actor: Actor,
+    Form(form): Form<PlanMealForm>,
+) -> impl IntoResponse {
+    if !(0..7).contains(&form.weekday) {
+        return (StatusCode::BAD_REQUEST, "Unknown weekday").into_response();
+    }
+    match pool.plan_meal(form.weekday, form.recipe, &actor).await {
+        Ok(_) => StatusCode::CREATED.into_response(),
+        Err(err) => {
+            tracing::error!(err = %err, "failed to plan meal");
+            error_fragment::store_error(&err, "Failed to plan meal")
+        }
+    }
+}
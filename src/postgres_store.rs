@@ -0,0 +1,1491 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, NaiveDateTime};
+use sqlx::{FromRow, PgConnection, PgExecutor, PgPool};
+
+use crate::actor::Actor;
+use crate::item::{Item, Quantity, State};
+use crate::item_event::{EventKind, ItemEvent};
+use crate::location::Location;
+use crate::meal_plan::{Need, PlannedMeal};
+use crate::page::{Cursor, Page, PageRequest};
+use crate::purchase::{Price, Purchase};
+use crate::recipe::{Ingredient, Recipe};
+use crate::search::SearchQuery;
+use crate::shop::{Aisle, Placements, Shop};
+use crate::store::{
+    Carts, CheckOut, History, Locations, MealPlans, Purchases, Recipes, Shops, Store, StoreError,
+    Users, check_aisle_order, check_merge,
+};
+use crate::user::User;
+
+/// Store backed by PostgreSQL, selected by a `postgres://` DSN. Behaves like
+/// [`crate::store::SqliteItemStore`], the conformance tests hold both to the same contract.
+///
+/// The queries are checked at runtime, the compile time checked macros can only check
+/// against one database.
+#[derive(Clone)]
+pub struct PostgresItemStore {
+    pool: PgPool,
+}
+
+#[derive(FromRow)]
+struct ItemRow {
+    id: i64,
+    name: String,
+    quantity: f64,
+    unit: String,
+    state: i64,
+    location_id: Option<i64>,
+    expires_on: Option<NaiveDate>,
+    par_quantity: Option<f64>,
+    version: i64,
+}
+
+impl From<ItemRow> for Item {
+    fn from(row: ItemRow) -> Self {
+        Item::new(
+            row.id,
+            row.name,
+            row.quantity,
+            row.unit.into(),
+            row.state.into(),
+        )
+        .with_location(row.location_id)
+        .with_expiry(row.expires_on)
+        .with_par(row.par_quantity)
+        .with_version(row.version)
+    }
+}
+
+#[derive(FromRow)]
+struct IngredientRow {
+    id: i64,
+    recipe_id: i64,
+    item_id: i64,
+    name: String,
+    quantity: f64,
+    unit: String,
+}
+
+impl From<IngredientRow> for Ingredient {
+    fn from(row: IngredientRow) -> Self {
+        Ingredient {
+            id: row.id,
+            recipe_id: row.recipe_id,
+            item_id: row.item_id,
+            name: row.name,
+            quantity: row.quantity,
+            unit: row.unit.into(),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct PurchaseRow {
+    id: i64,
+    item_id: i64,
+    name: String,
+    quantity: f64,
+    unit: String,
+    price_cents: i64,
+    shop: Option<String>,
+    purchased_on: NaiveDate,
+}
+
+impl From<PurchaseRow> for Purchase {
+    fn from(row: PurchaseRow) -> Self {
+        Purchase {
+            id: row.id,
+            item_id: row.item_id,
+            name: row.name,
+            quantity: row.quantity,
+            unit: row.unit.into(),
+            price: Price(row.price_cents),
+            shop: row.shop,
+            purchased_on: row.purchased_on,
+        }
+    }
+}
+
+#[async_trait]
+impl Store<Item> for PostgresItemStore {
+    async fn create(&self, record: Item, actor: &Actor) -> Result<i64, StoreError> {
+        let mut tx = self.pool.begin().await?;
+        let id = insert_item(&mut tx, &record, actor).await?;
+        record_event(&mut tx, id, EventKind::Created, None, Some(&record), actor).await?;
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
+    async fn create_many(&self, records: Vec<Item>, actor: &Actor) -> Result<Vec<i64>, StoreError> {
+        let mut tx = self.pool.begin().await?;
+        let mut ids = Vec::with_capacity(records.len());
+        for record in &records {
+            let id = insert_item(&mut tx, record, actor).await?;
+            record_event(&mut tx, id, EventKind::Created, None, Some(record), actor).await?;
+            ids.push(id);
+        }
+        tx.commit().await?;
+
+        Ok(ids)
+    }
+
+    async fn delete(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin().await?;
+        let Some(previous) = fetch_item(&mut *tx, id, actor).await? else {
+            return Err(StoreError::NotFound);
+        };
+        // Recipes keep the ingredient under the name the item had last
+        sqlx::query(r#"UPDATE ingredient SET name = $1 WHERE item_id = $2"#)
+            .bind(&previous.name)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(r#"DELETE FROM item WHERE id = $1"#)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        record_event(
+            &mut tx,
+            id,
+            EventKind::Deleted,
+            Some(&previous),
+            None,
+            actor,
+        )
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn update(&self, record: Item, actor: &Actor) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin().await?;
+        save_item(&mut tx, &record, actor).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn consume(&self, id: i64, amount: f64, actor: &Actor) -> Result<(), StoreError> {
+        self.adjust_quantity(id, -amount, actor).await
+    }
+
+    async fn restock(&self, id: i64, amount: f64, actor: &Actor) -> Result<(), StoreError> {
+        self.adjust_quantity(id, amount, actor).await
+    }
+
+    async fn merge(&self, into: i64, from: &[i64], actor: &Actor) -> Result<(), StoreError> {
+        check_merge(into, from)?;
+        let mut tx = self.pool.begin().await?;
+        let ids: Vec<i64> = std::iter::once(into).chain(from.iter().copied()).collect();
+        sqlx::query(r#"SELECT id FROM item WHERE id = ANY($1) AND household_id = $2 FOR UPDATE"#)
+            .bind(&ids)
+            .bind(actor.household_id())
+            .execute(&mut *tx)
+            .await?;
+        let mut items = Vec::with_capacity(ids.len());
+        for id in ids {
+            let Some(item) = fetch_item(&mut *tx, id, actor).await? else {
+                tx.rollback().await?;
+                return Err(StoreError::NotFound);
+            };
+            items.push(item);
+        }
+        let (target, others) = items.split_first().expect("the target is read first");
+        let Some(merged) = target.merged_with(others) else {
+            tx.rollback().await?;
+            return Err(StoreError::Validation(
+                "the items are measured in different kinds of units".to_string(),
+            ));
+        };
+
+        update_item(&mut tx, &merged, actor).await?;
+        record_event(
+            &mut tx,
+            into,
+            EventKind::Updated,
+            Some(target),
+            Some(&merged),
+            actor,
+        )
+        .await?;
+        for other in others {
+            fold_item(&mut tx, into, other, actor).await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn read(&self, id: i64, actor: &Actor) -> Result<Item, StoreError> {
+        let record = fetch_item(&self.pool, id, actor)
+            .await?
+            .ok_or(StoreError::NotFound)?;
+
+        Ok(record)
+    }
+
+    async fn read_many_from_state(
+        &self,
+        state: State,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        let records = sqlx::query_as::<_, ItemRow>(
+            r#"SELECT id, name, quantity, unit, state, location_id, expires_on, par_quantity, version FROM item WHERE state = $1 AND household_id = $2"#,
+        )
+        .bind(state.id())
+        .bind(actor.household_id())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records.into_iter().map(Item::from).collect())
+    }
+
+    async fn read_many_from_state_in_location(
+        &self,
+        state: State,
+        location: i64,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        let records = sqlx::query_as::<_, ItemRow>(
+            r#"WITH RECURSIVE scope (id) AS (
+                SELECT id FROM location WHERE id = $2 AND household_id = $3
+                UNION
+                SELECT location.id FROM location JOIN scope ON location.parent_id = scope.id
+            )
+            SELECT id, name, quantity, unit, state, location_id, expires_on, par_quantity, version
+            FROM item
+            WHERE state = $1 AND household_id = $3 AND location_id IN (SELECT id FROM scope)"#,
+        )
+        .bind(state.id())
+        .bind(location)
+        .bind(actor.household_id())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records.into_iter().map(Item::from).collect())
+    }
+
+    async fn read_page(
+        &self,
+        state: State,
+        request: &PageRequest,
+        actor: &Actor,
+    ) -> Result<Page<Item>, StoreError> {
+        #[derive(FromRow)]
+        struct PageRow {
+            #[sqlx(flatten)]
+            item: ItemRow,
+            sort_text: String,
+            sort_number: f64,
+        }
+
+        let (after_text, after_number, after_id) = match &request.after {
+            Some(cursor) => (
+                Some(cursor.text.as_str()),
+                Some(cursor.number),
+                Some(cursor.id),
+            ),
+            None => (None, None, None),
+        };
+        // One more than a page tells whether another page follows. The "C" collation
+        // orders names by code point like sqlite does.
+        let limit = request.limit.saturating_add(1);
+        let rows = sqlx::query_as::<_, PageRow>(
+            r#"WITH RECURSIVE scope (id) AS (
+                SELECT id FROM location WHERE id = $2::BIGINT AND household_id = $4
+                UNION
+                SELECT location.id FROM location JOIN scope ON location.parent_id = scope.id
+            )
+            SELECT id, name, quantity, unit, state, location_id, expires_on, par_quantity, version,
+                sort_text, sort_number
+            FROM (
+                SELECT *,
+                    COALESCE(CASE $3 WHEN 'name' THEN lower(name) END, '') COLLATE "C" AS sort_text,
+                    COALESCE(CASE $3
+                        WHEN 'quantity' THEN quantity * CASE unit WHEN 'kg' THEN 1000 WHEN 'l' THEN 1000 ELSE 1 END
+                        WHEN 'updated' THEN -EXTRACT(EPOCH FROM updated_at)::DOUBLE PRECISION
+                        WHEN 'expiry' THEN (COALESCE(expires_on, DATE '9999-12-31') - DATE '1970-01-01')::DOUBLE PRECISION
+                    END, 0.0) AS sort_number
+                FROM item
+                WHERE state = $1 AND household_id = $4
+                AND ($2::BIGINT IS NULL OR location_id IN (SELECT id FROM scope))
+            ) AS sorted
+            WHERE $5::BIGINT IS NULL OR (sort_text, sort_number, id) > ($6::TEXT COLLATE "C", $7::DOUBLE PRECISION, $5::BIGINT)
+            ORDER BY sort_text, sort_number, id
+            LIMIT $8"#,
+        )
+        .bind(state.id())
+        .bind(request.location)
+        .bind(request.sort.to_string())
+        .bind(actor.household_id())
+        .bind(after_id)
+        .bind(after_text)
+        .bind(after_number)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let has_more = rows.len() as i64 > request.limit;
+        let mut next = None;
+        let mut records = Vec::with_capacity(rows.len());
+        for row in rows.into_iter().take(request.limit as usize) {
+            next = Some(Cursor {
+                text: row.sort_text,
+                number: row.sort_number,
+                id: row.item.id,
+            });
+            records.push(row.item.into());
+        }
+        let next = next.filter(|_| has_more);
+
+        Ok(Page { records, next })
+    }
+
+    async fn read_expiring_until(
+        &self,
+        until: NaiveDate,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        let records = sqlx::query_as::<_, ItemRow>(
+            r#"SELECT id, name, quantity, unit, state, location_id, expires_on, par_quantity, version
+            FROM item
+            WHERE state = $1 AND expires_on <= $2 AND household_id = $3
+            ORDER BY expires_on, name"#,
+        )
+        .bind(State::STOCK.id())
+        .bind(until)
+        .bind(actor.household_id())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records.into_iter().map(Item::from).collect())
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        state: State,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        // Without an index to narrow down the candidates every item of the state is scored,
+        // a household's pantry is small enough for that
+        let query = SearchQuery::new(query);
+        let candidates = self.read_many_from_state(state, actor).await?;
+        if query.is_empty() {
+            return Ok(candidates);
+        }
+
+        let mut scored: Vec<(f64, Item)> = candidates
+            .into_iter()
+            .filter_map(|item| query.score(&item.name).map(|score| (score, item)))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        Ok(scored.into_iter().map(|(_, item)| item).collect())
+    }
+}
+
+#[async_trait]
+impl Locations for PostgresItemStore {
+    async fn create_location(&self, location: Location, actor: &Actor) -> Result<i64, StoreError> {
+        let id = sqlx::query_scalar(
+            r#"INSERT INTO location ( name, parent_id, household_id )
+            SELECT $1, $2, $3
+            WHERE $2::BIGINT IS NULL OR EXISTS (SELECT 1 FROM location WHERE id = $2 AND household_id = $3)
+            RETURNING id"#,
+        )
+        .bind(location.name)
+        .bind(location.parent_id)
+        .bind(actor.household_id())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(StoreError::NotFound)?;
+
+        Ok(id)
+    }
+
+    async fn delete_location(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let result = sqlx::query(r#"DELETE FROM location WHERE id = $1 AND household_id = $2"#)
+            .bind(id)
+            .bind(actor.household_id())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn read_locations(&self, actor: &Actor) -> Result<Vec<Location>, StoreError> {
+        let records = sqlx::query_as::<_, (i64, String, Option<i64>)>(
+            r#"SELECT id, name, parent_id FROM location WHERE household_id = $1 ORDER BY name"#,
+        )
+        .bind(actor.household_id())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|(id, name, parent_id)| Location::new(id, name, parent_id))
+            .collect())
+    }
+}
+
+#[async_trait]
+impl History for PostgresItemStore {
+    async fn read_history(
+        &self,
+        item_id: i64,
+        actor: &Actor,
+    ) -> Result<Vec<ItemEvent>, StoreError> {
+        let records = sqlx::query_as::<
+            _,
+            (
+                i64,
+                i64,
+                String,
+                Option<String>,
+                Option<String>,
+                Option<String>,
+                NaiveDateTime,
+            ),
+        >(
+            r#"SELECT id, item_id, kind, old_value, new_value, actor, created_at
+            FROM item_event
+            WHERE item_id = $1 AND household_id = $2
+            ORDER BY id DESC"#,
+        )
+        .bind(item_id)
+        .bind(actor.household_id())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(
+                |(id, item_id, kind, old_value, new_value, actor, created_at)| ItemEvent {
+                    id,
+                    item_id,
+                    kind: kind.into(),
+                    old_value,
+                    new_value,
+                    actor,
+                    created_at,
+                },
+            )
+            .collect())
+    }
+}
+
+#[async_trait]
+impl Users for PostgresItemStore {
+    async fn create_user(
+        &self,
+        username: &str,
+        password_hash: &str,
+        household: &str,
+    ) -> Result<i64, StoreError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(r#"INSERT INTO household ( name ) VALUES ($1) ON CONFLICT (name) DO NOTHING"#)
+            .bind(household)
+            .execute(&mut *tx)
+            .await?;
+        let id = sqlx::query_scalar(
+            r#"INSERT INTO "user" ( username, password_hash, household_id )
+            SELECT $1, $2, id FROM household WHERE name = $3
+            RETURNING id"#,
+        )
+        .bind(username)
+        .bind(password_hash)
+        .bind(household)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
+    async fn read_user_by_name(&self, username: &str) -> Result<Option<User>, StoreError> {
+        let record = sqlx::query_as::<_, (i64, String, String, i64)>(
+            r#"SELECT id, username, password_hash, household_id FROM "user" WHERE username = $1"#,
+        )
+        .bind(username)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.map(user_from_row))
+    }
+
+    async fn create_session(
+        &self,
+        token: &str,
+        user_id: i64,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin().await?;
+        // Piggyback the cleanup of expired sessions on every sign in
+        sqlx::query(r#"DELETE FROM session WHERE expires_at <= now() AT TIME ZONE 'UTC'"#)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(r#"INSERT INTO session ( token, user_id, expires_at ) VALUES ($1, $2, $3)"#)
+            .bind(token)
+            .bind(user_id)
+            .bind(expires_at)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn read_session_user(&self, token: &str) -> Result<Option<User>, StoreError> {
+        let record = sqlx::query_as::<_, (i64, String, String, i64)>(
+            r#"SELECT "user".id, "user".username, "user".password_hash, "user".household_id
+            FROM session JOIN "user" ON "user".id = session.user_id
+            WHERE session.token = $1 AND session.expires_at > now() AT TIME ZONE 'UTC'"#,
+        )
+        .bind(token)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record.map(user_from_row))
+    }
+
+    async fn delete_session(&self, token: &str) -> Result<(), StoreError> {
+        sqlx::query(r#"DELETE FROM session WHERE token = $1"#)
+            .bind(token)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn user_from_row((id, username, password_hash, household_id): (i64, String, String, i64)) -> User {
+    User {
+        id,
+        username,
+        password_hash,
+        household_id,
+    }
+}
+
+#[async_trait]
+impl Recipes for PostgresItemStore {
+    async fn create_recipe(&self, name: &str, actor: &Actor) -> Result<i64, StoreError> {
+        let id = sqlx::query_scalar(
+            r#"INSERT INTO recipe ( name, household_id ) VALUES ($1, $2) RETURNING id"#,
+        )
+        .bind(name)
+        .bind(actor.household_id())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn update_recipe(&self, id: i64, name: &str, actor: &Actor) -> Result<(), StoreError> {
+        let result =
+            sqlx::query(r#"UPDATE recipe SET name = $1 WHERE id = $2 AND household_id = $3"#)
+                .bind(name)
+                .bind(id)
+                .bind(actor.household_id())
+                .execute(&self.pool)
+                .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn delete_recipe(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let result = sqlx::query(r#"DELETE FROM recipe WHERE id = $1 AND household_id = $2"#)
+            .bind(id)
+            .bind(actor.household_id())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn read_recipe(&self, id: i64, actor: &Actor) -> Result<Recipe, StoreError> {
+        let (id, name) = sqlx::query_as::<_, (i64, String)>(
+            r#"SELECT id, name FROM recipe WHERE id = $1 AND household_id = $2"#,
+        )
+        .bind(id)
+        .bind(actor.household_id())
+        .fetch_one(&self.pool)
+        .await?;
+        let mut recipe = Recipe::new(id, name);
+        recipe.ingredients = sqlx::query_as::<_, IngredientRow>(
+            r#"SELECT ingredient.id, ingredient.recipe_id, ingredient.item_id, COALESCE(item.name, ingredient.name) AS name, ingredient.quantity, ingredient.unit
+            FROM ingredient LEFT JOIN item ON item.id = ingredient.item_id
+            WHERE ingredient.recipe_id = $1
+            ORDER BY ingredient.id"#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(Ingredient::from)
+        .collect();
+
+        Ok(recipe)
+    }
+
+    async fn read_recipes(&self, actor: &Actor) -> Result<Vec<Recipe>, StoreError> {
+        let household = actor.household_id();
+        let mut recipes: Vec<Recipe> = sqlx::query_as::<_, (i64, String)>(
+            r#"SELECT id, name FROM recipe WHERE household_id = $1 ORDER BY name"#,
+        )
+        .bind(household)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|(id, name)| Recipe::new(id, name))
+        .collect();
+        let ingredients = sqlx::query_as::<_, IngredientRow>(
+            r#"SELECT ingredient.id, ingredient.recipe_id, ingredient.item_id, COALESCE(item.name, ingredient.name) AS name, ingredient.quantity, ingredient.unit
+            FROM ingredient
+            LEFT JOIN item ON item.id = ingredient.item_id
+            JOIN recipe ON recipe.id = ingredient.recipe_id
+            WHERE recipe.household_id = $1
+            ORDER BY ingredient.id"#,
+        )
+        .bind(household)
+        .fetch_all(&self.pool)
+        .await?;
+
+        for ingredient in ingredients {
+            if let Some(recipe) = recipes
+                .iter_mut()
+                .find(|recipe| recipe.id == ingredient.recipe_id)
+            {
+                recipe.ingredients.push(ingredient.into());
+            }
+        }
+
+        Ok(recipes)
+    }
+
+    async fn create_ingredient(
+        &self,
+        ingredient: Ingredient,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        let id = sqlx::query_scalar(
+            r#"INSERT INTO ingredient ( recipe_id, item_id, name, quantity, unit )
+            SELECT $1, item.id, item.name, $3, $4 FROM item
+            WHERE item.id = $2 AND item.household_id = $5
+            AND EXISTS (SELECT 1 FROM recipe WHERE id = $1 AND household_id = $5)
+            RETURNING id"#,
+        )
+        .bind(ingredient.recipe_id)
+        .bind(ingredient.item_id)
+        .bind(ingredient.quantity)
+        .bind(ingredient.unit.to_string())
+        .bind(actor.household_id())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(StoreError::NotFound)?;
+
+        Ok(id)
+    }
+
+    async fn delete_ingredient(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let result = sqlx::query(
+            r#"DELETE FROM ingredient
+            WHERE id = $1 AND recipe_id IN (SELECT id FROM recipe WHERE household_id = $2)"#,
+        )
+        .bind(id)
+        .bind(actor.household_id())
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MealPlans for PostgresItemStore {
+    async fn read_meal_plan(&self, actor: &Actor) -> Result<Vec<PlannedMeal>, StoreError> {
+        let records = sqlx::query_as::<_, (i64, i64, i64, String)>(
+            r#"SELECT meal_plan.id, meal_plan.weekday, meal_plan.recipe_id, recipe.name
+            FROM meal_plan JOIN recipe ON recipe.id = meal_plan.recipe_id
+            WHERE meal_plan.household_id = $1
+            ORDER BY meal_plan.weekday, meal_plan.id"#,
+        )
+        .bind(actor.household_id())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|(id, weekday, recipe_id, recipe_name)| PlannedMeal {
+                id,
+                weekday,
+                recipe_id,
+                recipe_name,
+            })
+            .collect())
+    }
+
+    async fn plan_meal(
+        &self,
+        weekday: i64,
+        recipe_id: i64,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        let id = sqlx::query_scalar(
+            r#"INSERT INTO meal_plan ( weekday, recipe_id, household_id )
+            SELECT $1, $2, $3
+            WHERE EXISTS (SELECT 1 FROM recipe WHERE id = $2 AND household_id = $3)
+            RETURNING id"#,
+        )
+        .bind(weekday)
+        .bind(recipe_id)
+        .bind(actor.household_id())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(StoreError::NotFound)?;
+
+        Ok(id)
+    }
+
+    async fn delete_planned_meal(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let result = sqlx::query(r#"DELETE FROM meal_plan WHERE id = $1 AND household_id = $2"#)
+            .bind(id)
+            .bind(actor.household_id())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn shop_for_meal_plan(&self, actor: &Actor) -> Result<Vec<Need>, StoreError> {
+        let mut tx = self.pool.begin().await?;
+        let household = actor.household_id();
+        // Serializes shopping runs of the household, two at once would both add the needs
+        sqlx::query(r#"SELECT pg_advisory_xact_lock($1)"#)
+            .bind(household)
+            .execute(&mut *tx)
+            .await?;
+        // A recipe planned twice brings its ingredients twice
+        let ingredients: Vec<Ingredient> = sqlx::query_as::<_, IngredientRow>(
+            r#"SELECT ingredient.id, ingredient.recipe_id, ingredient.item_id, COALESCE(item.name, ingredient.name) AS name, ingredient.quantity, ingredient.unit
+            FROM meal_plan
+            JOIN ingredient ON ingredient.recipe_id = meal_plan.recipe_id
+            LEFT JOIN item ON item.id = ingredient.item_id
+            WHERE meal_plan.household_id = $1"#,
+        )
+        .bind(household)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(Ingredient::from)
+        .collect();
+        let stock: Vec<Item> = sqlx::query_as::<_, ItemRow>(
+            r#"SELECT id, name, quantity, unit, state, location_id, expires_on, par_quantity, version FROM item WHERE state = $1 AND household_id = $2"#,
+        )
+        .bind(State::STOCK.id())
+        .bind(household)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(Item::from)
+        .collect();
+
+        let needs = crate::meal_plan::needs(&ingredients, &stock);
+        for need in &needs {
+            add_to_shopping(&mut tx, &need.name, None, need.missing, None, actor).await?;
+        }
+        tx.commit().await?;
+
+        Ok(needs)
+    }
+}
+
+#[async_trait]
+impl Purchases for PostgresItemStore {
+    async fn record_purchase(&self, purchase: Purchase, actor: &Actor) -> Result<i64, StoreError> {
+        let mut conn = self.pool.acquire().await?;
+        insert_purchase(&mut conn, &purchase, actor).await
+    }
+
+    async fn buy(
+        &self,
+        record: Item,
+        purchase: Purchase,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        let mut tx = self.pool.begin().await?;
+        save_item(&mut tx, &record, actor).await?;
+        let id = insert_purchase(&mut tx, &purchase, actor).await?;
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
+    async fn read_purchases(&self, actor: &Actor) -> Result<Vec<Purchase>, StoreError> {
+        let records = sqlx::query_as::<_, PurchaseRow>(
+            r#"SELECT id, item_id, name, quantity, unit, price_cents, shop, purchased_on
+            FROM purchase WHERE household_id = $1
+            ORDER BY purchased_on DESC, id DESC"#,
+        )
+        .bind(actor.household_id())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records.into_iter().map(Purchase::from).collect())
+    }
+}
+
+#[async_trait]
+impl Carts for PostgresItemStore {
+    async fn set_in_cart(
+        &self,
+        item_id: i64,
+        in_cart: bool,
+        actor: &Actor,
+    ) -> Result<(), StoreError> {
+        let result = sqlx::query(
+            r#"UPDATE item SET in_cart = $1 WHERE id = $2 AND state = $3 AND household_id = $4"#,
+        )
+        .bind(in_cart)
+        .bind(item_id)
+        .bind(State::SHOPPING.id())
+        .bind(actor.household_id())
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn read_cart(&self, actor: &Actor) -> Result<Vec<i64>, StoreError> {
+        let ids = sqlx::query_scalar(
+            r#"SELECT id FROM item WHERE in_cart AND state = $1 AND household_id = $2 ORDER BY id"#,
+        )
+        .bind(State::SHOPPING.id())
+        .bind(actor.household_id())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    async fn check_out(&self, actor: &Actor) -> Result<usize, StoreError> {
+        let mut tx = self.pool.begin().await?;
+        let cart: Vec<Item> = sqlx::query_as::<_, ItemRow>(
+            r#"SELECT id, name, quantity, unit, state, location_id, expires_on, par_quantity, version
+            FROM item WHERE in_cart AND state = $1 AND household_id = $2 ORDER BY id FOR UPDATE"#,
+        )
+        .bind(State::SHOPPING.id())
+        .bind(actor.household_id())
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(Item::from)
+        .collect();
+        let stocked: Vec<Item> = sqlx::query_as::<_, ItemRow>(
+            r#"SELECT id, name, quantity, unit, state, location_id, expires_on, par_quantity, version
+            FROM item WHERE state = $1 AND household_id = $2 FOR UPDATE"#,
+        )
+        .bind(State::STOCK.id())
+        .bind(actor.household_id())
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(Item::from)
+        .collect();
+        let count = cart.len();
+        let check_out = CheckOut::plan(cart, &stocked);
+
+        for (into, items, merged) in &check_out.merges {
+            update_item(&mut tx, merged, actor).await?;
+            record_event(
+                &mut tx,
+                into.id,
+                EventKind::Updated,
+                Some(into),
+                Some(merged),
+                actor,
+            )
+            .await?;
+            for item in items {
+                fold_item(&mut tx, into.id, item, actor).await?;
+            }
+        }
+        for (item, moved) in &check_out.moves {
+            update_item(&mut tx, moved, actor).await?;
+            record_event(
+                &mut tx,
+                item.id,
+                EventKind::Updated,
+                Some(item),
+                Some(moved),
+                actor,
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl Shops for PostgresItemStore {
+    async fn create_shop(&self, name: &str, actor: &Actor) -> Result<i64, StoreError> {
+        let id = sqlx::query_scalar(
+            r#"INSERT INTO shop ( name, household_id ) VALUES ($1, $2) RETURNING id"#,
+        )
+        .bind(name)
+        .bind(actor.household_id())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn delete_shop(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let result = sqlx::query(r#"DELETE FROM shop WHERE id = $1 AND household_id = $2"#)
+            .bind(id)
+            .bind(actor.household_id())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn read_shops(&self, actor: &Actor) -> Result<Vec<Shop>, StoreError> {
+        let shops = sqlx::query_as::<_, (i64, String)>(
+            r#"SELECT id, name FROM shop WHERE household_id = $1 ORDER BY name, id"#,
+        )
+        .bind(actor.household_id())
+        .fetch_all(&self.pool)
+        .await?;
+        let aisles = sqlx::query_as::<_, (i64, i64, String)>(
+            r#"SELECT aisle.id, aisle.shop_id, aisle.name
+            FROM aisle JOIN shop ON shop.id = aisle.shop_id
+            WHERE shop.household_id = $1
+            ORDER BY aisle.position, aisle.id"#,
+        )
+        .bind(actor.household_id())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(shops
+            .into_iter()
+            .map(|(id, name)| Shop {
+                id,
+                name,
+                aisles: aisles
+                    .iter()
+                    .filter(|(_, shop_id, _)| *shop_id == id)
+                    .map(|(id, shop_id, name)| Aisle {
+                        id: *id,
+                        shop_id: *shop_id,
+                        name: name.clone(),
+                    })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    async fn create_aisle(
+        &self,
+        shop_id: i64,
+        name: &str,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        let id = sqlx::query_scalar(
+            r#"INSERT INTO aisle ( shop_id, name, position )
+            SELECT $1, $2, COALESCE((SELECT MAX(position) FROM aisle WHERE shop_id = $1), -1) + 1
+            WHERE EXISTS (SELECT 1 FROM shop WHERE id = $1 AND household_id = $3)
+            RETURNING id"#,
+        )
+        .bind(shop_id)
+        .bind(name)
+        .bind(actor.household_id())
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(StoreError::NotFound)?;
+
+        Ok(id)
+    }
+
+    async fn delete_aisle(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let result = sqlx::query(
+            r#"DELETE FROM aisle
+            WHERE id = $1 AND shop_id IN (SELECT id FROM shop WHERE household_id = $2)"#,
+        )
+        .bind(id)
+        .bind(actor.household_id())
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn reorder_aisles(
+        &self,
+        shop_id: i64,
+        order: &[i64],
+        actor: &Actor,
+    ) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin().await?;
+        // Locking the shop keeps concurrent reorders from interleaving
+        let shop: Option<i64> = sqlx::query_scalar(
+            r#"SELECT id FROM shop WHERE id = $1 AND household_id = $2 FOR UPDATE"#,
+        )
+        .bind(shop_id)
+        .bind(actor.household_id())
+        .fetch_optional(&mut *tx)
+        .await?;
+        if shop.is_none() {
+            return Err(StoreError::NotFound);
+        }
+        let aisles: Vec<Aisle> = sqlx::query_as::<_, (i64, i64, String)>(
+            r#"SELECT id, shop_id, name FROM aisle WHERE shop_id = $1"#,
+        )
+        .bind(shop_id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|(id, shop_id, name)| Aisle { id, shop_id, name })
+        .collect();
+        check_aisle_order(&aisles, order)?;
+
+        for (position, id) in (0_i64..).zip(order) {
+            sqlx::query(r#"UPDATE aisle SET position = $1 WHERE id = $2"#)
+                .bind(position)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn place_item(
+        &self,
+        item_id: i64,
+        shop_id: i64,
+        aisle_id: Option<i64>,
+        actor: &Actor,
+    ) -> Result<(), StoreError> {
+        let Some(aisle_id) = aisle_id else {
+            sqlx::query(
+                r#"DELETE FROM item_aisle
+                WHERE item_id = $1 AND shop_id = $2
+                AND shop_id IN (SELECT id FROM shop WHERE household_id = $3)"#,
+            )
+            .bind(item_id)
+            .bind(shop_id)
+            .bind(actor.household_id())
+            .execute(&self.pool)
+            .await?;
+            return Ok(());
+        };
+        let result = sqlx::query(
+            r#"INSERT INTO item_aisle ( item_id, shop_id, aisle_id )
+            SELECT $1, $2, $3
+            WHERE EXISTS (SELECT 1 FROM item WHERE id = $1 AND household_id = $4)
+            AND EXISTS (
+                SELECT 1 FROM aisle JOIN shop ON shop.id = aisle.shop_id
+                WHERE aisle.id = $3 AND aisle.shop_id = $2 AND shop.household_id = $4
+            )
+            ON CONFLICT (item_id, shop_id) DO UPDATE SET aisle_id = excluded.aisle_id"#,
+        )
+        .bind(item_id)
+        .bind(shop_id)
+        .bind(aisle_id)
+        .bind(actor.household_id())
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn read_placements(&self, shop_id: i64, actor: &Actor) -> Result<Placements, StoreError> {
+        let records = sqlx::query_as::<_, (i64, i64)>(
+            r#"SELECT item_aisle.item_id, item_aisle.aisle_id
+            FROM item_aisle JOIN shop ON shop.id = item_aisle.shop_id
+            WHERE item_aisle.shop_id = $1 AND shop.household_id = $2"#,
+        )
+        .bind(shop_id)
+        .bind(actor.household_id())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records.into_iter().collect())
+    }
+}
+
+async fn fetch_item<'e>(
+    executor: impl PgExecutor<'e>,
+    id: i64,
+    actor: &Actor,
+) -> Result<Option<Item>, StoreError> {
+    let record = sqlx::query_as::<_, ItemRow>(
+        r#"SELECT id, name, quantity, unit, state, location_id, expires_on, par_quantity, version FROM item WHERE id = $1 AND household_id = $2"#,
+    )
+    .bind(id)
+    .bind(actor.household_id())
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(record.map(Item::from))
+}
+
+async fn insert_item(
+    conn: &mut PgConnection,
+    record: &Item,
+    actor: &Actor,
+) -> Result<i64, StoreError> {
+    // A location of another household inserts nothing
+    let id = sqlx::query_scalar(
+        r#"INSERT INTO item ( name, quantity, unit, state, location_id, expires_on, par_quantity, household_id )
+        SELECT $1, $2, $3, $4, $5, $6, $7, $8
+        WHERE $5::BIGINT IS NULL OR EXISTS (SELECT 1 FROM location WHERE id = $5 AND household_id = $8)
+        RETURNING id"#,
+    )
+    .bind(&record.name)
+    .bind(record.quantity)
+    .bind(record.unit.to_string())
+    .bind(record.state.id())
+    .bind(record.location_id)
+    .bind(record.expires_on)
+    .bind(record.par_quantity)
+    .bind(actor.household_id())
+    .fetch_optional(conn)
+    .await?
+    .ok_or(StoreError::NotFound)?;
+
+    Ok(id)
+}
+
+/// Writes the item unless its row moved past `record.version`, returns whether it did. An
+/// item moved to another state leaves the cart. A location of another household is not
+/// found.
+async fn update_item(
+    conn: &mut PgConnection,
+    record: &Item,
+    actor: &Actor,
+) -> Result<bool, StoreError> {
+    let result = sqlx::query(
+        r#"UPDATE item SET name = $1, quantity = $2, unit = $3, state = $4, location_id = $5, expires_on = $6, par_quantity = $7, in_cart = in_cart AND state = $4, version = version + 1
+        WHERE id = $8 AND version = $9
+        AND ($5::BIGINT IS NULL OR EXISTS (SELECT 1 FROM location WHERE id = $5 AND household_id = $10))"#,
+    )
+    .bind(&record.name)
+    .bind(record.quantity)
+    .bind(record.unit.to_string())
+    .bind(record.state.id())
+    .bind(record.location_id)
+    .bind(record.expires_on)
+    .bind(record.par_quantity)
+    .bind(record.id)
+    .bind(record.version)
+    .bind(actor.household_id())
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() > 0 {
+        return Ok(true);
+    }
+
+    // Nothing written, either the version moved on or the location is not the household's
+    match record.location_id {
+        Some(location) if !has_location(conn, location, actor).await? => Err(StoreError::NotFound),
+        _ => Ok(false),
+    }
+}
+
+async fn has_location(conn: &mut PgConnection, id: i64, actor: &Actor) -> Result<bool, StoreError> {
+    let found = sqlx::query_scalar(
+        r#"SELECT EXISTS (SELECT 1 FROM location WHERE id = $1 AND household_id = $2)"#,
+    )
+    .bind(id)
+    .bind(actor.household_id())
+    .fetch_one(conn)
+    .await?;
+
+    Ok(found)
+}
+
+/// Writes an update of the item with its event and restocking, see [`Store::update`].
+async fn save_item(
+    conn: &mut PgConnection,
+    record: &Item,
+    actor: &Actor,
+) -> Result<(), StoreError> {
+    let Some(previous) = fetch_item(&mut *conn, record.id, actor).await? else {
+        return Err(StoreError::NotFound);
+    };
+
+    if previous.version != record.version || !update_item(conn, record, actor).await? {
+        return Err(StoreError::Conflict);
+    }
+    record_event(
+        conn,
+        record.id,
+        EventKind::Updated,
+        Some(&previous),
+        Some(record),
+        actor,
+    )
+    .await?;
+    restock_shortfall(conn, &previous, record, actor).await
+}
+
+async fn insert_purchase(
+    conn: &mut PgConnection,
+    purchase: &Purchase,
+    actor: &Actor,
+) -> Result<i64, StoreError> {
+    let id = sqlx::query_scalar(
+        r#"INSERT INTO purchase ( item_id, name, quantity, unit, price_cents, shop, purchased_on, household_id )
+        SELECT $1, $2, $3, $4, $5, $6, $7, $8
+        WHERE EXISTS (SELECT 1 FROM item WHERE id = $1 AND household_id = $8)
+        RETURNING id"#,
+    )
+    .bind(purchase.item_id)
+    .bind(&purchase.name)
+    .bind(purchase.quantity)
+    .bind(purchase.unit.to_string())
+    .bind(purchase.price.0)
+    .bind(&purchase.shop)
+    .bind(purchase.purchased_on)
+    .bind(actor.household_id())
+    .fetch_optional(conn)
+    .await?
+    .ok_or(StoreError::NotFound)?;
+
+    Ok(id)
+}
+
+/// Deletes `other` once folded into the item `into`, which takes over its ingredients and
+/// its aisles in shops where it has none of its own.
+async fn fold_item(
+    conn: &mut PgConnection,
+    into: i64,
+    other: &Item,
+    actor: &Actor,
+) -> Result<(), StoreError> {
+    sqlx::query(r#"UPDATE ingredient SET item_id = $1 WHERE item_id = $2"#)
+        .bind(into)
+        .bind(other.id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        r#"INSERT INTO item_aisle ( item_id, shop_id, aisle_id )
+        SELECT $1, shop_id, aisle_id FROM item_aisle WHERE item_id = $2
+        ON CONFLICT DO NOTHING"#,
+    )
+    .bind(into)
+    .bind(other.id)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(r#"DELETE FROM item WHERE id = $1"#)
+        .bind(other.id)
+        .execute(&mut *conn)
+        .await?;
+    record_event(conn, other.id, EventKind::Deleted, Some(other), None, actor).await
+}
+
+async fn record_event(
+    conn: &mut PgConnection,
+    item_id: i64,
+    kind: EventKind,
+    old: Option<&Item>,
+    new: Option<&Item>,
+    actor: &Actor,
+) -> Result<(), StoreError> {
+    sqlx::query(
+        r#"INSERT INTO item_event ( item_id, kind, old_value, new_value, actor, household_id ) VALUES ($1, $2, $3, $4, $5, $6)"#,
+    )
+    .bind(item_id)
+    .bind(kind.to_string())
+    .bind(old.map(Item::to_string))
+    .bind(new.map(Item::to_string))
+    .bind(actor.name())
+    .bind(actor.household_id())
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Puts the shortfall on the shopping list when an update dropped the quantity below par.
+async fn restock_shortfall(
+    conn: &mut PgConnection,
+    previous: &Item,
+    record: &Item,
+    actor: &Actor,
+) -> Result<(), StoreError> {
+    if let Some(shortfall) = record.shortfall_since(previous) {
+        add_to_shopping(
+            conn,
+            &record.name,
+            record.location_id,
+            shortfall,
+            Some(record.id),
+            actor,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Makes sure the shopping list holds at least `shortfall` of `name`, topping up an entry of
+/// that name already on the list instead of adding a second one. A new entry is placed in
+/// the aisles of the stock item `aisles_of`.
+async fn add_to_shopping(
+    conn: &mut PgConnection,
+    name: &str,
+    location_id: Option<i64>,
+    shortfall: Quantity,
+    aisles_of: Option<i64>,
+    actor: &Actor,
+) -> Result<(), StoreError> {
+    let existing = sqlx::query_as::<_, ItemRow>(
+        r#"SELECT id, name, quantity, unit, state, location_id, expires_on, par_quantity, version
+        FROM item
+        WHERE state = $1 AND lower(trim(name)) = lower(trim($2)) AND household_id = $3
+        ORDER BY id"#,
+    )
+    .bind(State::SHOPPING.id())
+    .bind(name)
+    .bind(actor.household_id())
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for previous in existing.into_iter().map(Item::from) {
+        let Some(needed) = shortfall.convert(previous.unit) else {
+            continue;
+        };
+        if previous.quantity < needed.amount {
+            let record = Item {
+                quantity: needed.amount,
+                ..previous.clone()
+            };
+            update_item(conn, &record, actor).await?;
+            record_event(
+                conn,
+                record.id,
+                EventKind::Updated,
+                Some(&previous),
+                Some(&record),
+                actor,
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+
+    let record = Item::new(
+        0,
+        name.to_string(),
+        shortfall.amount,
+        shortfall.unit,
+        State::SHOPPING,
+    )
+    .with_location(location_id);
+    let id = insert_item(conn, &record, actor).await?;
+    if let Some(aisles_of) = aisles_of {
+        sqlx::query(
+            r#"INSERT INTO item_aisle ( item_id, shop_id, aisle_id )
+            SELECT $1, shop_id, aisle_id FROM item_aisle WHERE item_id = $2"#,
+        )
+        .bind(id)
+        .bind(aisles_of)
+        .execute(&mut *conn)
+        .await?;
+    }
+    record_event(conn, id, EventKind::Created, None, Some(&record), actor).await?;
+
+    Ok(())
+}
+
+impl PostgresItemStore {
+    // The relative update keeps concurrent changes from overwriting each other, locking the
+    // row up front makes concurrent adjustments queue behind each other.
+    async fn adjust_quantity(&self, id: i64, delta: f64, actor: &Actor) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin().await?;
+        let previous = sqlx::query_as::<_, ItemRow>(
+            r#"SELECT id, name, quantity, unit, state, location_id, expires_on, par_quantity, version FROM item WHERE id = $1 AND household_id = $2 FOR UPDATE"#,
+        )
+        .bind(id)
+        .bind(actor.household_id())
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(previous) = previous.map(Item::from) else {
+            return Err(StoreError::NotFound);
+        };
+
+        sqlx::query(
+            r#"UPDATE item SET quantity = GREATEST(quantity + $1, 0), version = version + 1 WHERE id = $2"#,
+        )
+        .bind(delta)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+        let record = fetch_item(&mut *tx, id, actor)
+            .await?
+            .ok_or(StoreError::NotFound)?;
+        record_event(
+            &mut tx,
+            id,
+            EventKind::Updated,
+            Some(&previous),
+            Some(&record),
+            actor,
+        )
+        .await?;
+        restock_shortfall(&mut tx, &previous, &record, actor).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn new(dsn: &str) -> Self {
+        let pool = PgPool::connect(dsn)
+            .await
+            .inspect_err(|err| tracing::error!("{err} - dsn {dsn}"))
+            .expect("PgPool for ItemStore couldn't be initialized");
+        sqlx::migrate!("./migrations/postgres")
+            .run(&pool)
+            .await
+            .expect("migrations failed to be executed");
+
+        Self { pool }
+    }
+}
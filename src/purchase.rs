@@ -0,0 +1,146 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+
+use crate::item::{Quantity, Unit, UnitFamily, normalized_name};
+
+/// An amount of money in cents, prices are entered and shown with two decimals.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Default)]
+pub struct Price(pub i64);
+
+impl Display for Price {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:02}", self.0 / 100, self.0 % 100)
+    }
+}
+
+impl From<i64> for Price {
+    fn from(cents: i64) -> Self {
+        Self(cents)
+    }
+}
+
+impl FromStr for Price {
+    type Err = String;
+
+    /// Reads "3", "3.5" or "3,50", at most two decimals and never negative.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || "Enter a price like 3.49".to_string();
+        let s = s.trim().replace(',', ".");
+        let (whole, fraction) = s.split_once('.').unwrap_or((&s, ""));
+        let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty() || !digits(whole) || !digits(fraction) || fraction.len() > 2 {
+            return Err(invalid());
+        }
+        let whole: i64 = whole.parse().map_err(|_| invalid())?;
+        let fraction: i64 = format!("{fraction:0<2}").parse().map_err(|_| invalid())?;
+        whole
+            .checked_mul(100)
+            .and_then(|cents| cents.checked_add(fraction))
+            .map(Price)
+            .ok_or_else(invalid)
+    }
+}
+
+/// What was paid for an item when it moved from the shopping list to the stock.
+#[derive(Clone, Debug)]
+pub struct Purchase {
+    pub id: i64,
+    /// The item bought, it may have been deleted since
+    pub item_id: i64,
+    /// Name of the item when it was bought
+    pub name: String,
+    pub quantity: f64,
+    pub unit: Unit,
+    pub price: Price,
+    pub shop: Option<String>,
+    pub purchased_on: NaiveDate,
+}
+
+impl Purchase {
+    pub fn measure(&self) -> Quantity {
+        Quantity::new(self.quantity, self.unit)
+    }
+
+    /// Price per kilogram, litre or piece, comparable between pack sizes. `None` when
+    /// nothing was bought.
+    pub fn unit_price(&self) -> Option<(Price, Unit)> {
+        let unit = match self.unit.family() {
+            UnitFamily::Mass => Unit::Kilogram,
+            UnitFamily::Volume => Unit::Litre,
+            UnitFamily::Count => Unit::Piece,
+        };
+        let amount = self.measure().convert(unit)?.amount;
+        (amount > 0.0).then(|| (Price((self.price.0 as f64 / amount).round() as i64), unit))
+    }
+}
+
+/// Money spent on the purchases sharing a label.
+pub struct Spend {
+    pub label: String,
+    pub total: Price,
+    pub purchases: usize,
+}
+
+/// Spend per calendar month, the latest month first.
+pub fn spend_per_month(purchases: &[Purchase]) -> Vec<Spend> {
+    let mut months = totals(purchases.iter().map(|purchase| {
+        let month = purchase.purchased_on.format("%Y-%m").to_string();
+        (month, purchase.price)
+    }));
+    months.sort_by(|a, b| b.label.cmp(&a.label));
+    months
+}
+
+/// Spend per item, the most expensive first. Purchases of items with the same name add up
+/// even when the item was deleted and added again in between.
+pub fn spend_per_item(purchases: &[Purchase]) -> Vec<Spend> {
+    let mut items = totals(
+        purchases
+            .iter()
+            .map(|purchase| (purchase.name.trim().to_string(), purchase.price)),
+    );
+    items.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.label.cmp(&b.label)));
+    items
+}
+
+/// Spend per shop, the most expensive first. Purchases without a shop are left out.
+pub fn spend_per_shop(purchases: &[Purchase]) -> Vec<Spend> {
+    let mut shops = totals(purchases.iter().filter_map(|purchase| {
+        let shop = purchase.shop.as_ref()?;
+        Some((shop.trim().to_string(), purchase.price))
+    }));
+    shops.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.label.cmp(&b.label)));
+    shops
+}
+
+/// Purchases of the item named `name`, oldest first.
+pub fn price_history<'a>(name: &str, purchases: &'a [Purchase]) -> Vec<&'a Purchase> {
+    let key = normalized_name(name);
+    let mut history: Vec<&Purchase> = purchases
+        .iter()
+        .filter(|purchase| normalized_name(&purchase.name) == key)
+        .collect();
+    history.sort_by_key(|purchase| (purchase.purchased_on, purchase.id));
+    history
+}
+
+/// Adds up the prices per label, labels that only differ in case count as one and keep the
+/// spelling they first appear with. A total too large for a `Price` stays at the largest one.
+fn totals(prices: impl Iterator<Item = (String, Price)>) -> Vec<Spend> {
+    let mut totals: BTreeMap<String, Spend> = BTreeMap::new();
+    for (label, price) in prices {
+        let spend = totals
+            .entry(normalized_name(&label))
+            .or_insert_with(|| Spend {
+                label,
+                total: Price::default(),
+                purchases: 0,
+            });
+        spend.total.0 = spend.total.0.saturating_add(price.0);
+        spend.purchases += 1;
+    }
+    totals.into_values().collect()
+}
@@ -0,0 +1,65 @@
+use crate::item::{Item, Quantity, State, Unit, normalized_name};
+
+pub struct Recipe {
+    pub id: i64,
+    pub name: String,
+    pub ingredients: Vec<Ingredient>,
+}
+
+impl Recipe {
+    pub fn new(id: i64, name: String) -> Self {
+        Self {
+            id,
+            name,
+            ingredients: Vec::new(),
+        }
+    }
+
+    /// Whether the stock covers every ingredient.
+    pub fn can_cook(&self, stock: &[Item]) -> bool {
+        self.ingredients
+            .iter()
+            .all(|ingredient| ingredient.missing(stock).is_none())
+    }
+}
+
+/// Stock of everything named `name`, summed over the stock items whose unit converts to
+/// `unit`, e.g. the same food stored in two places.
+pub fn stock_of(name: &str, unit: Unit, stock: &[Item]) -> Quantity {
+    let name = normalized_name(name);
+    let amount = stock
+        .iter()
+        .filter(|item| item.state == State::STOCK && normalized_name(&item.name) == name)
+        .filter_map(|item| item.measure().convert(unit))
+        .map(|quantity| quantity.amount)
+        .sum();
+    Quantity::new(amount, unit)
+}
+
+#[derive(Clone)]
+pub struct Ingredient {
+    pub id: i64,
+    pub recipe_id: i64,
+    /// Pantry item the ingredient was picked from, it may be deleted since
+    pub item_id: i64,
+    /// Name of the linked item, the name it had last once it is deleted
+    pub name: String,
+    pub quantity: f64,
+    pub unit: Unit,
+}
+
+impl Ingredient {
+    pub fn required(&self) -> Quantity {
+        Quantity::new(self.quantity, self.unit)
+    }
+
+    pub fn available(&self, stock: &[Item]) -> Quantity {
+        stock_of(&self.name, self.unit, stock)
+    }
+
+    /// How much is lacking to cook the recipe, `None` when there is enough.
+    pub fn missing(&self, stock: &[Item]) -> Option<Quantity> {
+        let missing = self.quantity - self.available(stock).amount;
+        (missing > 0.0).then(|| Quantity::new(missing, self.unit))
+    }
+}
@@ -0,0 +1,117 @@
+use crate::actor::Actor;
+use crate::error_fragment;
+use crate::item::Quantity;
+use crate::purchase::{self, Price, Purchase, Spend};
+use crate::store::PurchaseStore;
+use askama::Template;
+use axum::extract::Query;
+use axum::response::{Html, Response};
+use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+#[derive(Template)]
+#[template(path = "reports.html")]
+struct ReportsTemplate {
+    months: Vec<Spend>,
+    items: Vec<Spend>,
+    shops: Vec<Spend>,
+}
+
+#[debug_handler(state = crate::AppState)]
+pub async fn show_reports(State(pool): State<PurchaseStore>, actor: Actor) -> impl IntoResponse {
+    let purchases = match pool.read_purchases(&actor).await {
+        Ok(purchases) => purchases,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read purchases");
+            return error_fragment::store_error(&err, "Failed to get purchases");
+        }
+    };
+
+    HtmlTemplate(ReportsTemplate {
+        months: purchase::spend_per_month(&purchases),
+        items: purchase::spend_per_item(&purchases),
+        shops: purchase::spend_per_shop(&purchases),
+    })
+    .into_response()
+}
+
+struct PricePointTemplate {
+    purchased_on: NaiveDate,
+    quantity: Quantity,
+    price: Price,
+    /// e.g. "2.49 / kg", empty when nothing was bought
+    unit_price: String,
+    shop: Option<String>,
+}
+
+impl PricePointTemplate {
+    fn new(purchase: &Purchase) -> Self {
+        Self {
+            purchased_on: purchase.purchased_on,
+            quantity: purchase.measure().normalized(),
+            price: purchase.price,
+            unit_price: purchase
+                .unit_price()
+                .map(|(price, unit)| format!("{price} / {unit}"))
+                .unwrap_or_default(),
+            shop: purchase.shop.clone(),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "price_history.html")]
+struct PriceHistoryTemplate {
+    name: String,
+    points: Vec<PricePointTemplate>,
+}
+
+#[derive(Deserialize)]
+pub struct PriceHistoryParameters {
+    item: String,
+}
+
+/// What was paid for an item over time, oldest first.
+#[debug_handler(state = crate::AppState)]
+pub async fn price_history(
+    State(pool): State<PurchaseStore>,
+    actor: Actor,
+    Query(query): Query<PriceHistoryParameters>,
+) -> impl IntoResponse {
+    let purchases = match pool.read_purchases(&actor).await {
+        Ok(purchases) => purchases,
+        Err(err) => {
+            tracing::error!(err = %err, item = query.item, "failed to read purchases");
+            return error_fragment::store_error(&err, "Failed to get the price history");
+        }
+    };
+
+    let points = purchase::price_history(&query.item, &purchases)
+        .into_iter()
+        .map(PricePointTemplate::new)
+        .collect();
+    HtmlTemplate(PriceHistoryTemplate {
+        name: query.item,
+        points,
+    })
+    .into_response()
+}
+
+struct HtmlTemplate<T>(T);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::item::normalized_name;
+
+/// Share of the query's trigrams an item name needs to contain to count as a match, low
+/// enough that a typo or two in a longer word still finds it.
+const MIN_SIMILARITY: f64 = 0.5;
+
+/// Parsed search box input, matching is case insensitive.
+pub struct SearchQuery {
+    /// Words of three or more characters, matched through their trigrams
+    trigrams: Vec<String>,
+    /// Shorter words, matched as the start of a word in the name
+    prefixes: Vec<String>,
+}
+
+impl SearchQuery {
+    pub fn new(query: &str) -> Self {
+        let mut trigrams = Vec::new();
+        let mut prefixes = Vec::new();
+        for word in normalized_name(query).split_whitespace() {
+            let chars: Vec<char> = word.chars().collect();
+            if chars.len() < 3 {
+                prefixes.push(word.to_string());
+                continue;
+            }
+            for window in chars.windows(3) {
+                let trigram: String = window.iter().collect();
+                if !trigrams.contains(&trigram) {
+                    trigrams.push(trigram);
+                }
+            }
+        }
+        Self { trigrams, prefixes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.trigrams.is_empty() && self.prefixes.is_empty()
+    }
+
+    /// FTS5 expression finding candidates sharing any trigram with the query, `None` when
+    /// the query has no word long enough for the trigram index.
+    pub fn fts_expression(&self) -> Option<String> {
+        if self.trigrams.is_empty() {
+            return None;
+        }
+        let phrases: Vec<String> = self
+            .trigrams
+            .iter()
+            .map(|trigram| format!("\"{}\"", trigram.replace('"', "\"\"")))
+            .collect();
+        Some(phrases.join(" OR "))
+    }
+
+    /// How well `name` matches from 0 to 1, `None` when it does not match at all.
+    pub fn score(&self, name: &str) -> Option<f64> {
+        let name = normalized_name(name);
+        let prefixes_match = self
+            .prefixes
+            .iter()
+            .all(|prefix| name.split_whitespace().any(|word| word.starts_with(prefix)));
+        if !prefixes_match {
+            return None;
+        }
+        if self.trigrams.is_empty() {
+            return Some(1.0);
+        }
+
+        let found = self
+            .trigrams
+            .iter()
+            .filter(|trigram| name.contains(trigram.as_str()))
+            .count();
+        let similarity = found as f64 / self.trigrams.len() as f64;
+        (similarity >= MIN_SIMILARITY).then_some(similarity)
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub struct Shop {
+    pub id: i64,
+    pub name: String,
+    /// In the order they are walked through
+    pub aisles: Vec<Aisle>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Aisle {
+    pub id: i64,
+    pub shop_id: i64,
+    pub name: String,
+}
+
+/// Where each item sits in a shop, aisle id keyed by item id.
+pub type Placements = HashMap<i64, i64>;
+
+impl Shop {
+    /// The aisle order with `aisle` swapped with its neighbour, `None` when it is already at
+    /// that end or not an aisle of this shop.
+    pub fn order_with_moved(&self, aisle: i64, up: bool) -> Option<Vec<i64>> {
+        let mut order: Vec<i64> = self.aisles.iter().map(|aisle| aisle.id).collect();
+        let index = order.iter().position(|id| *id == aisle)?;
+        let other = if up {
+            index.checked_sub(1)?
+        } else {
+            Some(index + 1).filter(|other| *other < order.len())?
+        };
+        order.swap(index, other);
+        Some(order)
+    }
+
+    /// Sorts the items into the aisles in walking order, items without an aisle of this shop
+    /// come last under `None`. Aisles without items are left out.
+    pub fn walk<T>(
+        &self,
+        items: Vec<T>,
+        aisle_of: impl Fn(&T) -> Option<i64>,
+    ) -> Vec<(Option<&Aisle>, Vec<T>)> {
+        let mut groups: Vec<(Option<&Aisle>, Vec<T>)> = self
+            .aisles
+            .iter()
+            .map(|aisle| (Some(aisle), Vec::new()))
+            .chain(std::iter::once((None, Vec::new())))
+            .collect();
+        let unplaced = self.aisles.len();
+        for item in items {
+            let index = aisle_of(&item)
+                .and_then(|id| self.aisles.iter().position(|aisle| aisle.id == id))
+                .unwrap_or(unplaced);
+            groups[index].1.push(item);
+        }
+        groups.retain(|(_, items)| !items.is_empty());
+        groups
+    }
+}
@@ -0,0 +1,53 @@
+use crate::actor::Actor;
+use crate::live_updates::LiveUpdates;
+use crate::meal_plan::Need;
+use crate::store::MealPlanStore;
+use askama::Template;
+use axum::response::{Html, Response};
+use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+
+#[derive(Template)]
+#[template(path = "meal_plan_shopping.html")]
+struct MealPlanShoppingTemplate {
+    needs: Vec<Need>,
+}
+
+#[debug_handler(state = crate::AppState)]
+pub async fn shop_meal_plan(
+    State(pool): State<MealPlanStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+) -> impl IntoResponse {
+    match pool.shop_for_meal_plan(&actor).await {
+        Ok(needs) => {
+            live.publish(&actor, &[crate::item::State::SHOPPING]);
+            HtmlTemplate(MealPlanShoppingTemplate { needs }).into_response()
+        }
+        Err(err) => {
+            tracing::error!(err = %err, "failed to shop for meal plan");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update the shopping list",
+            )
+                .into_response()
+        }
+    }
+}
+
+struct HtmlTemplate<T>(T);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
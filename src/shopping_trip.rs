@@ -0,0 +1,317 @@
+use crate::actor::Actor;
+use crate::error_fragment;
+use crate::item::{Item, State as ItemState, Unit};
+use crate::item_validation::{self, ItemFormErrors};
+use crate::live_updates::LiveUpdates;
+use crate::shop::Shop;
+use crate::state_items::ShopOptionTemplate;
+use crate::store::{CartStore, ItemStore, ShopStore, StoreError};
+use askama::Template;
+use axum::Form;
+use axum::extract::Query;
+use axum::extract::rejection::FormRejection;
+use axum::response::{Html, Response};
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+
+struct TripItemTemplate {
+    id: i64,
+    name: String,
+    quantity: f64,
+    unit: Unit,
+    version: i64,
+    in_cart: bool,
+}
+
+impl TripItemTemplate {
+    fn new(item: &Item, cart: &[i64]) -> Self {
+        Self {
+            id: item.id,
+            name: item.name.clone(),
+            quantity: item.quantity,
+            unit: item.unit,
+            version: item.version,
+            in_cart: cart.contains(&item.id),
+        }
+    }
+    fn row_class(&self) -> &'static str {
+        if self.in_cart {
+            "list-group-item-success"
+        } else {
+            ""
+        }
+    }
+
+    fn label_class(&self) -> &'static str {
+        if self.in_cart {
+            "text-decoration-line-through"
+        } else {
+            ""
+        }
+    }
+}
+
+struct TripGroupTemplate {
+    // Without a shop the list is a single group without a name
+    name: Option<String>,
+    items: Vec<TripItemTemplate>,
+}
+
+#[derive(Template)]
+#[template(path = "shopping_trip_list.html")]
+struct TripListTemplate {
+    shop: Option<i64>,
+    groups: Vec<TripGroupTemplate>,
+    in_cart: usize,
+}
+
+impl TripListTemplate {
+    fn has_items(&self) -> bool {
+        self.groups.iter().any(|group| !group.items.is_empty())
+    }
+}
+
+#[derive(Template)]
+#[template(path = "shopping_trip.html")]
+struct TripTemplate {
+    shops: Vec<ShopOptionTemplate>,
+    list: TripListTemplate,
+}
+
+/// The shopping list in the order the selected shop is walked through.
+async fn read_list(
+    pool: &ItemStore,
+    carts: &CartStore,
+    shop_store: &ShopStore,
+    shops: &[Shop],
+    shop: Option<i64>,
+    actor: &Actor,
+) -> Result<TripListTemplate, StoreError> {
+    let mut items = pool
+        .read_many_from_state(ItemState::SHOPPING, actor)
+        .await?;
+    items.sort_by_key(|item| (item.name.to_lowercase(), item.id));
+    let cart = carts.read_cart(actor).await?;
+    let items: Vec<TripItemTemplate> = items
+        .iter()
+        .map(|item| TripItemTemplate::new(item, &cart))
+        .collect();
+    let in_cart = items.iter().filter(|item| item.in_cart).count();
+
+    // A shop deleted in the meantime leaves the list unordered
+    let groups = match shop.and_then(|id| shops.iter().find(|shop| shop.id == id)) {
+        Some(shop) => {
+            let placements = shop_store.read_placements(shop.id, actor).await?;
+            shop.walk(items, |item| placements.get(&item.id).copied())
+                .into_iter()
+                .map(|(aisle, items)| TripGroupTemplate {
+                    name: Some(aisle.map_or_else(|| "No aisle".to_string(), |a| a.name.clone())),
+                    items,
+                })
+                .collect()
+        }
+        None => vec![TripGroupTemplate { name: None, items }],
+    };
+
+    Ok(TripListTemplate {
+        shop,
+        groups,
+        in_cart,
+    })
+}
+
+/// Answers a change made during the trip with the list as it is now.
+async fn render_list(
+    pool: &ItemStore,
+    carts: &CartStore,
+    shop_store: &ShopStore,
+    shop: Option<i64>,
+    actor: &Actor,
+) -> Response {
+    let list = match shop_store.read_shops(actor).await {
+        Ok(shops) => read_list(pool, carts, shop_store, &shops, shop, actor).await,
+        Err(err) => Err(err),
+    };
+    match list {
+        Ok(list) => HtmlTemplate(list).into_response(),
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read the shopping list");
+            error_fragment::store_error(&err, "Failed to get the shopping list")
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct TripParameters {
+    /// Walk the list through the aisles of this shop
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    shop: Option<i64>,
+}
+
+/// The shopping list laid out for a phone in the shop.
+#[debug_handler(state = crate::AppState)]
+pub async fn shopping_trip(
+    State(pool): State<ItemStore>,
+    State(carts): State<CartStore>,
+    State(shop_store): State<ShopStore>,
+    actor: Actor,
+    Query(query): Query<TripParameters>,
+) -> impl IntoResponse {
+    let shops = match shop_store.read_shops(&actor).await {
+        Ok(shops) => shops,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read shops");
+            return error_fragment::store_error(&err, "Failed to get shops");
+        }
+    };
+    match read_list(&pool, &carts, &shop_store, &shops, query.shop, &actor).await {
+        Ok(list) => {
+            let shops = ShopOptionTemplate::from_shops(&shops, list.shop);
+            HtmlTemplate(TripTemplate { shops, list }).into_response()
+        }
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read the shopping list");
+            error_fragment::store_error(&err, "Failed to get the shopping list")
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CartForm {
+    #[serde(default)]
+    in_cart: bool,
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    shop: Option<i64>,
+}
+
+/// Ticks the item off as in the cart, or puts it back on the list.
+#[debug_handler(state = crate::AppState)]
+pub async fn put_in_cart(
+    State(pool): State<ItemStore>,
+    State(carts): State<CartStore>,
+    State(shop_store): State<ShopStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Form(form): Form<CartForm>,
+) -> impl IntoResponse {
+    if let Err(err) = carts.set_in_cart(id, form.in_cart, &actor).await {
+        tracing::error!(err = %err, id, "failed to put item in the cart");
+        return error_fragment::store_error(&err, "Failed to tick the item off");
+    }
+    live.publish(&actor, &[ItemState::SHOPPING]);
+    render_list(&pool, &carts, &shop_store, form.shop, &actor).await
+}
+
+#[derive(Deserialize)]
+pub struct BoughtForm {
+    quantity: f64,
+    /// Version of the item the list was rendered from
+    version: i64,
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    shop: Option<i64>,
+}
+
+/// Changes the quantity of the item to what was actually bought.
+#[debug_handler(state = crate::AppState)]
+pub async fn adjust_bought(
+    State(pool): State<ItemStore>,
+    State(carts): State<CartStore>,
+    State(shop_store): State<ShopStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Path(id): Path<i64>,
+    form: Result<Form<BoughtForm>, FormRejection>,
+) -> impl IntoResponse {
+    let form = match form {
+        Ok(Form(form)) => form,
+        Err(rejection) => return ItemFormErrors::from_rejection(rejection).into_response(),
+    };
+    let item = match pool.read(id, &actor).await {
+        Ok(item) => item,
+        Err(err) => {
+            tracing::error!(err = %err, id, "failed to read item");
+            return error_fragment::store_error(&err, "Failed to change the quantity");
+        }
+    };
+    // Only what is still on the shopping list is bought on the trip
+    if item.state != ItemState::SHOPPING {
+        return error_fragment::store_error(&StoreError::NotFound, "Failed to change the quantity");
+    }
+    let state = item.state;
+    let bought = Item {
+        quantity: form.quantity,
+        version: form.version,
+        ..item
+    };
+    let bought = match item_validation::validate(bought, Some(state)) {
+        Ok(bought) => bought,
+        Err(errors) => return errors.into_response(),
+    };
+
+    if let Err(err) = pool.update(bought, &actor).await {
+        tracing::error!(err = %err, id, "failed to change the bought quantity");
+        return error_fragment::store_error(&err, "Failed to change the quantity");
+    }
+    live.publish(&actor, &[ItemState::SHOPPING]);
+    render_list(&pool, &carts, &shop_store, form.shop, &actor).await
+}
+
+#[derive(Deserialize)]
+pub struct CheckOutForm {
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    shop: Option<i64>,
+}
+
+/// Moves everything in the cart to the stock at once, the rest stays on the list. What was
+/// paid is left out on purpose, a price is only recorded when an item is bought on its own.
+#[debug_handler(state = crate::AppState)]
+pub async fn check_out(
+    State(pool): State<ItemStore>,
+    State(carts): State<CartStore>,
+    State(shop_store): State<ShopStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Form(form): Form<CheckOutForm>,
+) -> impl IntoResponse {
+    if !ItemState::SHOPPING.can_move_to(ItemState::STOCK) {
+        let err = StoreError::Validation(format!(
+            "items in {} can't be moved to {}",
+            ItemState::SHOPPING,
+            ItemState::STOCK
+        ));
+        return error_fragment::store_error(&err, "Failed to put the cart in stock");
+    }
+    match carts.check_out(&actor).await {
+        Ok(0) => {}
+        Ok(_) => live.publish(&actor, &[ItemState::SHOPPING, ItemState::STOCK]),
+        Err(err) => {
+            tracing::error!(err = %err, "failed to check out the cart");
+            return error_fragment::store_error(&err, "Failed to put the cart in stock");
+        }
+    }
+    render_list(&pool, &carts, &shop_store, form.shop, &actor).await
+}
+
+struct HtmlTemplate<T>(T);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::actor::Actor;
+use crate::error_fragment;
+use crate::item::State as ItemState;
+use crate::live_updates::LiveUpdates;
+use crate::shop::Shop;
+use crate::store::{ShopStore, StoreError};
+use askama::Template;
+use axum::response::{Html, Response};
+use axum::{
+    Form, debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+
+#[derive(Template)]
+#[template(path = "shops.html")]
+struct ShopsTemplate {
+    shops: Vec<Shop>,
+}
+
+async fn render(pool: &ShopStore, actor: &Actor) -> Response {
+    match pool.read_shops(actor).await {
+        Ok(shops) => HtmlTemplate(ShopsTemplate { shops }).into_response(),
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read shops");
+            error_fragment::store_error(&err, "Failed to get shops")
+        }
+    }
+}
+
+/// Names are required, blank ones are refused like a store would refuse them.
+fn name(name: &str) -> Result<&str, StoreError> {
+    match name.trim() {
+        "" => Err(StoreError::Validation("name is blank".to_string())),
+        name => Ok(name),
+    }
+}
+
+#[debug_handler]
+pub async fn list_shops(State(pool): State<ShopStore>, actor: Actor) -> impl IntoResponse {
+    render(&pool, &actor).await
+}
+
+#[derive(Deserialize)]
+pub struct NameForm {
+    name: String,
+}
+
+#[debug_handler(state = crate::AppState)]
+pub async fn create_shop(
+    State(pool): State<ShopStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Form(form): Form<NameForm>,
+) -> impl IntoResponse {
+    let created = match name(&form.name) {
+        Ok(name) => pool.create_shop(name, &actor).await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = created {
+        tracing::error!(err = %err, "failed to create shop");
+        return error_fragment::store_error(&err, "Failed to add the shop");
+    }
+    live.publish(&actor, &[ItemState::SHOPPING]);
+    render(&pool, &actor).await
+}
+
+/// Deletes the shop with its aisles, the shopping list no longer offers to sort by it.
+#[debug_handler(state = crate::AppState)]
+pub async fn delete_shop(
+    State(pool): State<ShopStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(err) = pool.delete_shop(id, &actor).await {
+        tracing::error!(err = %err, id, "failed to delete shop");
+        return error_fragment::store_error(&err, "Failed to delete the shop");
+    }
+    live.publish(&actor, &[ItemState::SHOPPING]);
+    render(&pool, &actor).await
+}
+
+#[debug_handler(state = crate::AppState)]
+pub async fn create_aisle(
+    State(pool): State<ShopStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Path(shop_id): Path<i64>,
+    Form(form): Form<NameForm>,
+) -> impl IntoResponse {
+    let created = match name(&form.name) {
+        Ok(name) => pool.create_aisle(shop_id, name, &actor).await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = created {
+        tracing::error!(err = %err, shop_id, "failed to create aisle");
+        return error_fragment::store_error(&err, "Failed to add the aisle");
+    }
+    live.publish(&actor, &[ItemState::SHOPPING]);
+    render(&pool, &actor).await
+}
+
+/// Deletes the aisle, the items placed in it are listed without an aisle again.
+#[debug_handler(state = crate::AppState)]
+pub async fn delete_aisle(
+    State(pool): State<ShopStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    if let Err(err) = pool.delete_aisle(id, &actor).await {
+        tracing::error!(err = %err, id, "failed to delete aisle");
+        return error_fragment::store_error(&err, "Failed to delete the aisle");
+    }
+    live.publish(&actor, &[ItemState::SHOPPING]);
+    render(&pool, &actor).await
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+#[derive(Deserialize)]
+pub struct MoveAisleForm {
+    direction: Direction,
+}
+
+/// Swaps the aisle with the one before or after it in the walking order.
+#[debug_handler(state = crate::AppState)]
+pub async fn move_aisle(
+    State(pool): State<ShopStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Form(form): Form<MoveAisleForm>,
+) -> impl IntoResponse {
+    let shops = match pool.read_shops(&actor).await {
+        Ok(shops) => shops,
+        Err(err) => {
+            tracing::error!(err = %err, id, "failed to read shops");
+            return error_fragment::store_error(&err, "Failed to move the aisle");
+        }
+    };
+    let Some(shop) = shops
+        .iter()
+        .find(|shop| shop.aisles.iter().any(|aisle| aisle.id == id))
+    else {
+        return error_fragment::store_error(&StoreError::NotFound, "Failed to move the aisle");
+    };
+    // Already first or last, nothing to swap with
+    let Some(order) = shop.order_with_moved(id, matches!(form.direction, Direction::Up)) else {
+        return render(&pool, &actor).await;
+    };
+
+    if let Err(err) = pool.reorder_aisles(shop.id, &order, &actor).await {
+        tracing::error!(err = %err, id, "failed to reorder aisles");
+        return error_fragment::store_error(&err, "Failed to move the aisle");
+    }
+    live.publish(&actor, &[ItemState::SHOPPING]);
+    render(&pool, &actor).await
+}
+
+#[derive(Deserialize)]
+pub struct PlaceItemForm {
+    /// No aisle takes the item out of the shop's aisles
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    aisle: Option<i64>,
+}
+
+#[debug_handler(state = crate::AppState)]
+pub async fn place_item(
+    State(pool): State<ShopStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    Path((shop_id, item_id)): Path<(i64, i64)>,
+    Form(form): Form<PlaceItemForm>,
+) -> impl IntoResponse {
+    if let Err(err) = pool.place_item(item_id, shop_id, form.aisle, &actor).await {
+        tracing::error!(err = %err, shop_id, item_id, "failed to place item");
+        return error_fragment::store_error(&err, "Failed to move the item to the aisle");
+    }
+    live.publish(&actor, &[ItemState::SHOPPING]);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+struct HtmlTemplate<T>(T);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::actor::Actor;
+use crate::error_fragment;
+use crate::store::{MealPlanStore, RecipeStore};
+use askama::Template;
+use axum::response::{Html, Response};
+use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+
+const DAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+pub struct MealTemplate {
+    id: i64,
+    recipe_name: String,
+}
+
+pub struct DayTemplate {
+    weekday: i64,
+    name: &'static str,
+    meals: Vec<MealTemplate>,
+}
+
+pub struct RecipeOptionTemplate {
+    id: i64,
+    name: String,
+}
+
+#[derive(Template)]
+#[template(path = "meal_plan.html")]
+struct MealPlanTemplate {
+    days: Vec<DayTemplate>,
+    recipes: Vec<RecipeOptionTemplate>,
+}
+
+#[debug_handler(state = crate::AppState)]
+pub async fn show_meal_plan(
+    State(pool): State<MealPlanStore>,
+    State(recipes): State<RecipeStore>,
+    actor: Actor,
+) -> impl IntoResponse {
+    let planned = match pool.read_meal_plan(&actor).await {
+        Ok(planned) => planned,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read meal plan");
+            return error_fragment::store_error(&err, "Failed to get meal plan");
+        }
+    };
+    let recipes = match recipes.read_recipes(&actor).await {
+        Ok(recipes) => recipes,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read recipes for meal plan");
+            return error_fragment::store_error(&err, "Failed to get recipes");
+        }
+    };
+
+    let mut days: Vec<DayTemplate> = DAY_NAMES
+        .iter()
+        .zip(0..)
+        .map(|(name, weekday)| DayTemplate {
+            weekday,
+            name,
+            meals: Vec::new(),
+        })
+        .collect();
+    for meal in planned {
+        if let Some(day) = days.iter_mut().find(|day| day.weekday == meal.weekday) {
+            day.meals.push(MealTemplate {
+                id: meal.id,
+                recipe_name: meal.recipe_name,
+            });
+        }
+    }
+
+    let template = MealPlanTemplate {
+        days,
+        recipes: recipes
+            .into_iter()
+            .map(|recipe| RecipeOptionTemplate {
+                id: recipe.id,
+                name: recipe.name,
+            })
+            .collect(),
+    };
+    HtmlTemplate(template).into_response()
+}
+
+struct HtmlTemplate<T>(T);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
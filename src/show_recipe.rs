@@ -0,0 +1,115 @@
+use crate::actor::Actor;
+use crate::error_fragment;
+use crate::item::{Item, Quantity, State as ItemState, Unit};
+use crate::recipe::Ingredient;
+use crate::store::{ItemStore, RecipeStore};
+use askama::Template;
+use axum::response::{Html, Response};
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+
+pub struct IngredientTemplate {
+    id: i64,
+    name: String,
+    required: Quantity,
+    available: Quantity,
+    missing: Option<Quantity>,
+}
+
+impl IngredientTemplate {
+    fn new(ingredient: &Ingredient, stock: &[Item]) -> Self {
+        Self {
+            id: ingredient.id,
+            name: ingredient.name.clone(),
+            required: ingredient.required().normalized(),
+            available: ingredient.available(stock).normalized(),
+            missing: ingredient.missing(stock).map(Quantity::normalized),
+        }
+    }
+}
+
+pub struct ItemOptionTemplate {
+    id: i64,
+    label: String,
+}
+
+/// The "can I cook this?" view: every ingredient next to what the stock holds.
+#[derive(Template)]
+#[template(path = "recipe.html")]
+struct RecipeTemplate {
+    id: i64,
+    name: String,
+    can_cook: bool,
+    ingredients: Vec<IngredientTemplate>,
+    items: Vec<ItemOptionTemplate>,
+    units: &'static [Unit],
+}
+
+#[debug_handler(state = crate::AppState)]
+pub async fn show_recipe(
+    State(pool): State<RecipeStore>,
+    State(items): State<ItemStore>,
+    actor: Actor,
+    Path(id): Path<i64>,
+) -> impl IntoResponse {
+    let recipe = match pool.read_recipe(id, &actor).await {
+        Ok(recipe) => recipe,
+        Err(err) => {
+            tracing::error!(err = %err, id, "failed to read recipe");
+            return error_fragment::store_error(&err, "Failed to get recipe");
+        }
+    };
+    let mut all_items = Vec::new();
+    for &state in ItemState::all() {
+        match items.read_many_from_state(state, &actor).await {
+            Ok(mut found) => all_items.append(&mut found),
+            Err(err) => {
+                tracing::error!(err = %err, state = %state, "failed to read items for recipe");
+                return error_fragment::store_error(&err, "Failed to get items");
+            }
+        }
+    }
+    all_items.sort_by_key(|item| item.name.to_lowercase());
+
+    let template = RecipeTemplate {
+        id: recipe.id,
+        can_cook: recipe.can_cook(&all_items),
+        ingredients: recipe
+            .ingredients
+            .iter()
+            .map(|ingredient| IngredientTemplate::new(ingredient, &all_items))
+            .collect(),
+        items: all_items
+            .iter()
+            .map(|item| ItemOptionTemplate {
+                id: item.id,
+                label: format!("{} ({})", item.name, item.state),
+            })
+            .collect(),
+        name: recipe.name,
+        units: Unit::ALL,
+    };
+    HtmlTemplate(template).into_response()
+}
+
+struct HtmlTemplate<T>(T);
+
+impl<T> IntoResponse for HtmlTemplate<T>
+where
+    T: Template,
+{
+    fn into_response(self) -> Response {
+        match self.0.render() {
+            Ok(html) => Html(html).into_response(),
+            Err(err) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to render template. Error: {err}"),
+            )
+                .into_response(),
+        }
+    }
+}
@@ -1,66 +1,190 @@
-use crate::store::ItemStore;
+use crate::actor::Actor;
+use crate::item::{Freshness, Item, Quantity};
+use crate::list_locations::LocationOptionTemplate;
+use crate::page::{Cursor, PAGE_SIZE, PageRequest, SortKey};
+use crate::shop::{Aisle, Placements, Shop};
+use crate::states::StateDefinition;
+use crate::store::{ItemStore, LocationStore, ShopStore};
 use askama::Template;
 use axum::extract::Query;
 use axum::response::{Html, Response};
 use axum::{debug_handler, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::NaiveDate;
 use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
 
-struct StatePresentation<'a> {
-    id: &'a str,
-    name: &'a str,
-    move_description: &'a str,
-    css_color: &'a str,
+struct StatePresentation {
+    id: &'static str,
+    name: &'static str,
+    move_description: String,
+    css_color: &'static str,
+    /// Offer the list as a download, see `export_shopping`
+    exportable: bool,
+    /// Offer to go through the list in the shop, see `shopping_trip`
+    trip: bool,
 }
 
-const STOCK_PRESENTATION: StatePresentation<'static> = StatePresentation {
-    id: "stock",
-    name: "Stock",
-    move_description: "Move to Stock",
-    css_color: "bg-success",
-};
+impl StatePresentation {
+    fn new(definition: &'static StateDefinition) -> Self {
+        Self {
+            id: &definition.slug,
+            name: &definition.name,
+            move_description: format!("Move to {}", definition.name),
+            css_color: &definition.css_color,
+            exportable: definition.state == crate::item::State::SHOPPING,
+            trip: definition.state == crate::item::State::SHOPPING,
+        }
+    }
+}
 
-const SHOPPING_PRESENTATION: StatePresentation<'static> = StatePresentation {
-    id: "shopping",
-    name: "Shopping",
-    move_description: "Move to Shopping",
-    css_color: "bg-warning",
-};
+pub struct ItemTemplate {
+    pub id: i64,
+    pub name: String,
+    pub quantity: Quantity,
+    pub location_id: Option<i64>,
+    pub expires_on: Option<NaiveDate>,
+    pub freshness: Freshness,
+    pub par: Option<Quantity>,
+    pub below_par: bool,
+    /// What a single press of the consume and restock buttons changes
+    pub step: Quantity,
+    /// Aisle of the item in the selected shop
+    pub aisle_id: Option<i64>,
+}
 
-const STOCK_TRANSITIONS: &[StatePresentation; 1] = &[SHOPPING_PRESENTATION];
-const SHOPPING_TRANSITIONS: &[StatePresentation; 1] = &[STOCK_PRESENTATION];
+impl ItemTemplate {
+    pub fn new(item: &Item, today: NaiveDate) -> Self {
+        Self {
+            id: item.id,
+            name: item.name.clone(),
+            quantity: item.measure().normalized(),
+            location_id: item.location_id,
+            expires_on: item.expires_on,
+            freshness: item.freshness(today),
+            par: item
+                .par_quantity
+                .map(|par| Quantity::new(par, item.unit).normalized()),
+            below_par: item.shortfall().is_some(),
+            step: Quantity::new(item.unit.step(), item.unit),
+            aisle_id: None,
+        }
+    }
+
+    pub fn is_in(&self, aisle: &Aisle) -> bool {
+        self.aisle_id == Some(aisle.id)
+    }
 
-struct ItemTemplate {
-    id: i64,
-    name: String,
-    quantity: f64,
+    pub fn row_class(&self) -> &'static str {
+        match self.freshness {
+            Freshness::Fresh => "",
+            Freshness::ExpiringSoon => "list-group-item-warning",
+            Freshness::Expired => "list-group-item-danger",
+        }
+    }
 }
 
-impl ItemTemplate {
-    fn new(id: i64, name: String, quantity: f64) -> Self {
-        Self { id, name, quantity }
+pub struct ShopOptionTemplate {
+    pub id: i64,
+    pub name: String,
+    pub selected: bool,
+}
+
+impl ShopOptionTemplate {
+    pub fn from_shops(shops: &[Shop], selected: Option<i64>) -> Vec<Self> {
+        shops
+            .iter()
+            .map(|shop| Self {
+                id: shop.id,
+                name: shop.name.clone(),
+                selected: selected == Some(shop.id),
+            })
+            .collect()
     }
 }
 
+struct ItemGroupTemplate {
+    // Ungrouped lists are rendered as a single group without a name
+    name: Option<String>,
+    items: Vec<ItemTemplate>,
+}
+
 #[derive(Template)]
 #[template(path = "state_items.html")]
 struct StateItemsTemplate {
-    state: StatePresentation<'static>,
-    items: Vec<ItemTemplate>,
-    transitions: &'static [StatePresentation<'static>; 1],
+    state: StatePresentation,
+    groups: Vec<ItemGroupTemplate>,
+    transitions: Vec<StatePresentation>,
+    locations: Vec<LocationOptionTemplate>,
+    /// Shops the list can be walked through, only offered for the shopping list
+    shops: Vec<ShopOptionTemplate>,
+    /// The selected shop
+    shop: Option<i64>,
+    /// Aisles of the selected shop, offered for every item
+    aisles: Vec<Aisle>,
+    group: bool,
+    sort: SortKey,
+    sort_options: &'static [SortKey],
+    query: String,
+    search: Option<String>,
+    next: Option<Cursor>,
 }
 
 impl StateItemsTemplate {
-    fn new(state: crate::item::State, items: Vec<ItemTemplate>) -> Self {
-        let (state, transitions) = match state {
-            crate::item::State::Stock => (STOCK_PRESENTATION, STOCK_TRANSITIONS),
-            crate::item::State::Shopping => (SHOPPING_PRESENTATION, SHOPPING_TRANSITIONS),
-        };
+    fn new(
+        query: &QueryParameters,
+        groups: Vec<ItemGroupTemplate>,
+        locations: Vec<LocationOptionTemplate>,
+        shops: &[Shop],
+        selected: Option<&Shop>,
+        next: Option<Cursor>,
+    ) -> Self {
+        let (state, transitions) = presentation(query.state);
         Self {
             state,
-            items,
+            groups,
             transitions,
+            locations,
+            shops: ShopOptionTemplate::from_shops(shops, selected.map(|shop| shop.id)),
+            shop: selected.map(|shop| shop.id),
+            aisles: selected.map(|shop| shop.aisles.clone()).unwrap_or_default(),
+            group: query.group,
+            sort: query.sort,
+            sort_options: SortKey::ALL,
+            query: query.to_string(),
+            search: query.q.clone(),
+            next,
         }
     }
+
+    fn has_items(&self) -> bool {
+        self.groups.iter().any(|group| !group.items.is_empty())
+    }
+}
+
+/// Items following a "load more" marker, appended to the list in place of the marker.
+#[derive(Template)]
+#[template(path = "state_items_page.html")]
+struct StateItemsPageTemplate {
+    state: StatePresentation,
+    items: Vec<ItemTemplate>,
+    transitions: Vec<StatePresentation>,
+    /// Lists walked through a shop are not paged, so pages never show aisles
+    shop: Option<i64>,
+    aisles: Vec<Aisle>,
+    query: String,
+    next: Option<Cursor>,
+}
+
+fn presentation(state: crate::item::State) -> (StatePresentation, Vec<StatePresentation>) {
+    // The query names the state by its slug, so it is always a configured one
+    let definition = state.definition().expect("queried states are configured");
+    let transitions = definition
+        .transitions
+        .iter()
+        .filter_map(|state| state.definition())
+        .map(StatePresentation::new)
+        .collect();
+    (StatePresentation::new(definition), transitions)
 }
 
 #[derive(Template)]
@@ -78,36 +202,212 @@ impl StateItemsErrorTemplate {
 #[derive(Deserialize)]
 pub struct QueryParameters {
     state: crate::item::State,
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    location: Option<i64>,
+    /// Order the shopping list by the aisles of this shop
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    shop: Option<i64>,
+    #[serde(default)]
+    group: bool,
+    #[serde(default)]
+    sort: SortKey,
+    /// Continue the list after this item, see [`Cursor`]. Its text is an item name, so it is
+    /// read without trimming
+    #[serde(default)]
+    after: Option<String>,
+    /// Search box input, sent along by `hx-include` rather than kept in the query string
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    q: Option<String>,
+}
+
+impl std::fmt::Display for QueryParameters {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "state={}", self.state)?;
+        if let Some(location) = self.location {
+            write!(f, "&location={location}")?;
+        }
+        if let Some(shop) = self.shop {
+            write!(f, "&shop={shop}")?;
+        }
+        if self.group {
+            write!(f, "&group=true")?;
+        }
+        if self.sort != SortKey::default() {
+            write!(f, "&sort={}", self.sort)?;
+        }
+        Ok(())
+    }
 }
 
-#[debug_handler]
+#[debug_handler(state = crate::AppState)]
 pub async fn state_items(
     State(pool): State<ItemStore>,
+    State(locations): State<LocationStore>,
+    State(shop_store): State<ShopStore>,
+    actor: Actor,
     Query(query): Query<QueryParameters>,
 ) -> impl IntoResponse {
-    let items = match pool.read_many_from_state(query.state).await {
+    let name = presentation(query.state).0.name;
+    let after = query.after.as_deref().filter(|after| !after.is_empty());
+    let after = match after.map(str::parse::<Cursor>).transpose() {
+        Ok(after) => after,
+        Err(err) => {
+            let template = StateItemsErrorTemplate::new(format!("Invalid page cursor: {err}."));
+            return HtmlTemplate(template, StatusCode::BAD_REQUEST).into_response();
+        }
+    };
+
+    let shops = if query.state == crate::item::State::SHOPPING {
+        shop_store.read_shops(&actor).await
+    } else {
+        Ok(Vec::new())
+    };
+    let shops = match shops {
+        Ok(shops) => shops,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read shops");
+            let template = StateItemsErrorTemplate::new(format!("Failed to get shops for {name}."));
+            return HtmlTemplate(template, StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+    // A shop deleted in the meantime leaves the list unordered
+    let shop = query
+        .shop
+        .and_then(|id| shops.iter().find(|shop| shop.id == id));
+    let placements = match shop {
+        Some(shop) => shop_store.read_placements(shop.id, &actor).await,
+        None => Ok(Placements::new()),
+    };
+    let placements = match placements {
+        Ok(placements) => placements,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read aisles of items");
+            let template =
+                StateItemsErrorTemplate::new(format!("Failed to get aisles for {name}."));
+            return HtmlTemplate(template, StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+
+    // Search results come by relevance and grouped lists need every item, so only the plain
+    // list is paged. Aisles group the list as well.
+    let items = match &query.q {
+        Some(search) => pool
+            .search(search, query.state, &actor)
+            .await
+            .map(|items| (items, None)),
+        None => {
+            let request = PageRequest {
+                sort: query.sort,
+                location: query.location,
+                after: after.clone(),
+                limit: if query.group || shop.is_some() {
+                    i64::MAX
+                } else {
+                    PAGE_SIZE
+                },
+            };
+            pool.read_page(query.state, &request, &actor)
+                .await
+                .map(|page| (page.records, page.next))
+        }
+    };
+    let (items, next) = match items {
         Ok(items) => items,
         Err(err) => {
             tracing::error!(err = %err, state = %query.state, "failed to read items from state");
-            let name = match query.state {
-                crate::item::State::Stock => STOCK_PRESENTATION.name,
-                crate::item::State::Shopping => SHOPPING_PRESENTATION.name,
-            };
-
             let template = StateItemsErrorTemplate::new(format!("Failed to get items in {name}."));
             return HtmlTemplate(template, StatusCode::INTERNAL_SERVER_ERROR).into_response();
         }
     };
 
-    let items = items
+    let locations = match locations.read_locations(&actor).await {
+        Ok(locations) => locations,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read locations");
+            let template =
+                StateItemsErrorTemplate::new(format!("Failed to get locations for {name}."));
+            return HtmlTemplate(template, StatusCode::INTERNAL_SERVER_ERROR).into_response();
+        }
+    };
+    let items = match (&query.q, query.location) {
+        (Some(_), Some(location)) => {
+            let scope = crate::location::subtree(&locations, location);
+            items
+                .into_iter()
+                .filter(|item| item.location_id.is_some_and(|id| scope.contains(&id)))
+                .collect()
+        }
+        _ => items,
+    };
+    let options = LocationOptionTemplate::from_locations(&locations, query.location);
+    let paths = crate::location::paths(&locations);
+
+    let today = chrono::Local::now().date_naive();
+    let items: Vec<ItemTemplate> = items
         .iter()
-        .map(|item| ItemTemplate::new(item.id, item.name.clone(), item.quantity))
+        .map(|item| ItemTemplate {
+            aisle_id: placements.get(&item.id).copied(),
+            ..ItemTemplate::new(item, today)
+        })
         .collect();
+    if after.is_some() {
+        let (state, transitions) = presentation(query.state);
+        let template = StateItemsPageTemplate {
+            state,
+            items,
+            transitions,
+            shop: None,
+            aisles: Vec::new(),
+            query: query.to_string(),
+            next,
+        };
+        return HtmlTemplate(template, StatusCode::OK).into_response();
+    }
+    let groups = if let Some(shop) = shop {
+        group_by_aisle(items, shop)
+    } else if query.group {
+        group_by_location(items, &paths)
+    } else {
+        vec![ItemGroupTemplate { name: None, items }]
+    };
 
-    let template = StateItemsTemplate::new(query.state, items);
+    let template = StateItemsTemplate::new(&query, groups, options, &shops, shop, next);
     HtmlTemplate(template, StatusCode::OK).into_response()
 }
 
+fn group_by_location(
+    items: Vec<ItemTemplate>,
+    paths: &HashMap<i64, String>,
+) -> Vec<ItemGroupTemplate> {
+    // Items without a location sort after every named location
+    let mut groups: BTreeMap<(bool, String), Vec<ItemTemplate>> = BTreeMap::new();
+    for item in items {
+        let key = match item.location_id.and_then(|id| paths.get(&id)) {
+            Some(path) => (false, path.clone()),
+            None => (true, "No location".to_string()),
+        };
+        groups.entry(key).or_default().push(item);
+    }
+
+    groups
+        .into_iter()
+        .map(|((_, name), items)| ItemGroupTemplate {
+            name: Some(name),
+            items,
+        })
+        .collect()
+}
+
+fn group_by_aisle(items: Vec<ItemTemplate>, shop: &Shop) -> Vec<ItemGroupTemplate> {
+    shop.walk(items, |item| item.aisle_id)
+        .into_iter()
+        .map(|(aisle, items)| ItemGroupTemplate {
+            name: Some(aisle.map_or_else(|| "No aisle".to_string(), |aisle| aisle.name.clone())),
+            items,
+        })
+        .collect()
+}
+
 struct HtmlTemplate<T>(T, StatusCode);
 
 impl<T> IntoResponse for HtmlTemplate<T>
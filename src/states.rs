@@ -0,0 +1,172 @@
+//! The states items can be in. Stock and shopping are built in and keep their roles, stock is
+//! what recipes cook from and shortfalls go on the shopping list. The configuration can add
+//! states like "Opened" or "Frozen" and decides where items of each state can be moved.
+//!
+//! The states are set once at startup and read from anywhere, [`State`] parses and prints
+//! itself through them.
+
+use crate::configuration::StateConfiguration;
+use crate::item::State;
+use std::sync::OnceLock;
+
+static STATES: OnceLock<States> = OnceLock::new();
+
+/// Makes `states` the ones every item can be in, before anything reads them.
+pub fn init(states: States) {
+    if STATES.set(states).is_err() {
+        panic!("item states are already set");
+    }
+}
+
+/// The configured states, the built-in ones when [`init`] was not called.
+pub fn states() -> &'static States {
+    STATES.get_or_init(States::default)
+}
+
+pub struct StateDefinition {
+    pub state: State,
+    /// Names the state in urls, forms, element ids and live update events
+    pub slug: String,
+    pub name: String,
+    pub css_color: String,
+    /// States its items can be moved to, staying put is always allowed
+    pub transitions: Vec<State>,
+}
+
+pub struct States {
+    definitions: Vec<StateDefinition>,
+    /// Every state in the order of `definitions`, handed out as a slice
+    all: Vec<State>,
+}
+
+impl Default for States {
+    fn default() -> Self {
+        Self::new(vec![
+            StateDefinition {
+                state: State::STOCK,
+                slug: "stock".to_string(),
+                name: "Stock".to_string(),
+                css_color: "bg-success".to_string(),
+                transitions: vec![State::SHOPPING],
+            },
+            StateDefinition {
+                state: State::SHOPPING,
+                slug: "shopping".to_string(),
+                name: "Shopping".to_string(),
+                css_color: "bg-warning".to_string(),
+                transitions: vec![State::STOCK],
+            },
+        ])
+    }
+}
+
+impl States {
+    fn new(definitions: Vec<StateDefinition>) -> Self {
+        let all = definitions
+            .iter()
+            .map(|definition| definition.state)
+            .collect();
+        Self { definitions, all }
+    }
+
+    /// The built-in states amended by the `[[states]]` of the configuration, added states
+    /// follow the built-in ones in the order they are configured.
+    pub fn from_configuration(configured: &[StateConfiguration]) -> Result<Self, String> {
+        let mut definitions = Self::default().definitions;
+        for entry in configured {
+            if !is_slug(&entry.slug) {
+                return Err(format!(
+                    "state slug {:?} may only hold lowercase letters, digits and dashes",
+                    entry.slug
+                ));
+            }
+            let slug_taken = definitions
+                .iter()
+                .any(|definition| definition.slug == entry.slug);
+            match definitions
+                .iter_mut()
+                .find(|definition| definition.state.id() == entry.id)
+            {
+                Some(built_in) if built_in.slug != entry.slug => {
+                    return Err(format!(
+                        "state {} is {}, its slug can't be changed",
+                        entry.id, built_in.slug
+                    ));
+                }
+                Some(built_in) => {
+                    if let Some(name) = &entry.name {
+                        built_in.name = name.clone();
+                    }
+                    if let Some(css_color) = &entry.css_color {
+                        built_in.css_color = css_color.clone();
+                    }
+                }
+                None if entry.id < 0 => {
+                    return Err(format!("state {} needs an id of 0 or more", entry.slug));
+                }
+                None if slug_taken => {
+                    return Err(format!("state slug {} is used twice", entry.slug));
+                }
+                None => definitions.push(StateDefinition {
+                    state: State::from(entry.id),
+                    slug: entry.slug.clone(),
+                    name: entry.name.clone().unwrap_or_else(|| entry.slug.clone()),
+                    css_color: entry
+                        .css_color
+                        .clone()
+                        .unwrap_or_else(|| "bg-secondary".to_string()),
+                    transitions: Vec::new(),
+                }),
+            }
+        }
+
+        // Transitions name states by slug, resolve them once every state is known
+        for entry in configured {
+            let Some(slugs) = &entry.transitions else {
+                continue;
+            };
+            let mut transitions = Vec::with_capacity(slugs.len());
+            for slug in slugs {
+                let target = definitions
+                    .iter()
+                    .find(|definition| &definition.slug == slug)
+                    .ok_or_else(|| format!("state {} moves to unknown state {slug}", entry.slug))?;
+                transitions.push(target.state);
+            }
+            let definition = definitions
+                .iter_mut()
+                .find(|definition| definition.slug == entry.slug)
+                .expect("every configured state was added above");
+            definition.transitions = transitions;
+        }
+
+        Ok(Self::new(definitions))
+    }
+
+    pub fn definitions(&self) -> &[StateDefinition] {
+        &self.definitions
+    }
+
+    pub fn all(&self) -> &[State] {
+        &self.all
+    }
+
+    pub fn get(&self, state: State) -> Option<&StateDefinition> {
+        self.definitions
+            .iter()
+            .find(|definition| definition.state == state)
+    }
+
+    pub fn by_slug(&self, slug: &str) -> Option<&StateDefinition> {
+        self.definitions
+            .iter()
+            .find(|definition| definition.slug == slug)
+    }
+}
+
+fn is_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
@@ -1,28 +1,314 @@
 use async_trait::async_trait;
-use sqlx::SqlitePool;
+use chrono::{NaiveDate, NaiveDateTime};
+use sqlx::{SqliteConnection, SqliteExecutor, SqlitePool};
 use std::{fmt::Display, sync::Arc};
 use thiserror::Error;
 
-use crate::item::{Item, State};
+use crate::actor::Actor;
+use crate::item::{Item, Quantity, State, duplicate_key};
+use crate::item_event::{EventKind, ItemEvent};
+use crate::location::Location;
+use crate::meal_plan::{Need, PlannedMeal};
+use crate::page::{Cursor, Page, PageRequest};
+use crate::purchase::Purchase;
+use crate::recipe::{Ingredient, Recipe};
+use crate::search::SearchQuery;
+use crate::shop::{Aisle, Placements, Shop};
+use crate::user::User;
 
 #[async_trait]
 pub trait Store<T> {
-    async fn create(&self, record: T) -> Result<i64, StoreError>;
-    async fn delete(&self, id: i64) -> Result<(), StoreError>;
-    async fn update(&self, record: T) -> Result<(), StoreError>;
-    async fn read(&self, id: i64) -> Result<T, StoreError>;
-    async fn read_many_from_state(&self, state: State) -> Result<Vec<T>, StoreError>;
+    async fn create(&self, record: T, actor: &Actor) -> Result<i64, StoreError>;
+    /// Creates all records in a single transaction, nothing is stored when one of them fails.
+    async fn create_many(&self, records: Vec<T>, actor: &Actor) -> Result<Vec<i64>, StoreError>;
+    async fn delete(&self, id: i64, actor: &Actor) -> Result<(), StoreError>;
+    async fn update(&self, record: T, actor: &Actor) -> Result<(), StoreError>;
+    /// Takes `amount` off the record's quantity in a single statement, stopping at zero.
+    async fn consume(&self, id: i64, amount: f64, actor: &Actor) -> Result<(), StoreError>;
+    /// Adds `amount` to the record's quantity in a single statement.
+    async fn restock(&self, id: i64, amount: f64, actor: &Actor) -> Result<(), StoreError>;
+    /// Folds the records `from` into `into` in a single transaction, see [`Item::merged_with`].
+    /// Recipes using one of them use `into` afterwards.
+    async fn merge(&self, into: i64, from: &[i64], actor: &Actor) -> Result<(), StoreError>;
+    async fn read(&self, id: i64, actor: &Actor) -> Result<T, StoreError>;
+    async fn read_many_from_state(&self, state: State, actor: &Actor)
+    -> Result<Vec<T>, StoreError>;
+    /// Records in `state` stored in `location` or any location nested inside it.
+    async fn read_many_from_state_in_location(
+        &self,
+        state: State,
+        location: i64,
+        actor: &Actor,
+    ) -> Result<Vec<T>, StoreError>;
+    /// A page of the records in `state` in the requested order.
+    async fn read_page(
+        &self,
+        state: State,
+        request: &PageRequest,
+        actor: &Actor,
+    ) -> Result<Page<T>, StoreError>;
+    /// Records in stock expiring on or before `until`, soonest first.
+    async fn read_expiring_until(
+        &self,
+        until: NaiveDate,
+        actor: &Actor,
+    ) -> Result<Vec<T>, StoreError>;
+    /// Records in `state` whose name matches `query`, best match first. Short words match
+    /// the start of a word, longer ones any part of the name with a typo or two.
+    async fn search(&self, query: &str, state: State, actor: &Actor) -> Result<Vec<T>, StoreError>;
+}
+
+#[async_trait]
+pub trait Locations {
+    async fn create_location(&self, location: Location, actor: &Actor) -> Result<i64, StoreError>;
+    /// Deletes the location together with the locations nested inside it.
+    async fn delete_location(&self, id: i64, actor: &Actor) -> Result<(), StoreError>;
+    async fn read_locations(&self, actor: &Actor) -> Result<Vec<Location>, StoreError>;
+}
+
+#[async_trait]
+pub trait History {
+    /// Changes made to the item, most recent first.
+    async fn read_history(&self, item_id: i64, actor: &Actor)
+    -> Result<Vec<ItemEvent>, StoreError>;
+}
+
+#[async_trait]
+pub trait Users {
+    /// Creates the user in the household with that name, creating the household if needed.
+    async fn create_user(
+        &self,
+        username: &str,
+        password_hash: &str,
+        household: &str,
+    ) -> Result<i64, StoreError>;
+    async fn read_user_by_name(&self, username: &str) -> Result<Option<User>, StoreError>;
+    async fn create_session(
+        &self,
+        token: &str,
+        user_id: i64,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), StoreError>;
+    /// The user owning an unexpired session.
+    async fn read_session_user(&self, token: &str) -> Result<Option<User>, StoreError>;
+    async fn delete_session(&self, token: &str) -> Result<(), StoreError>;
+}
+
+#[async_trait]
+pub trait Recipes {
+    async fn create_recipe(&self, name: &str, actor: &Actor) -> Result<i64, StoreError>;
+    async fn update_recipe(&self, id: i64, name: &str, actor: &Actor) -> Result<(), StoreError>;
+    /// Deletes the recipe together with its ingredients.
+    async fn delete_recipe(&self, id: i64, actor: &Actor) -> Result<(), StoreError>;
+    async fn read_recipe(&self, id: i64, actor: &Actor) -> Result<Recipe, StoreError>;
+    /// All recipes with their ingredients, ordered by name.
+    async fn read_recipes(&self, actor: &Actor) -> Result<Vec<Recipe>, StoreError>;
+    /// Adds the ingredient to its recipe, the recipe and the linked item have to belong to
+    /// the actor's household.
+    async fn create_ingredient(
+        &self,
+        ingredient: Ingredient,
+        actor: &Actor,
+    ) -> Result<i64, StoreError>;
+    async fn delete_ingredient(&self, id: i64, actor: &Actor) -> Result<(), StoreError>;
+}
+
+#[async_trait]
+pub trait MealPlans {
+    /// Planned meals ordered by weekday.
+    async fn read_meal_plan(&self, actor: &Actor) -> Result<Vec<PlannedMeal>, StoreError>;
+    async fn plan_meal(
+        &self,
+        weekday: i64,
+        recipe_id: i64,
+        actor: &Actor,
+    ) -> Result<i64, StoreError>;
+    async fn delete_planned_meal(&self, id: i64, actor: &Actor) -> Result<(), StoreError>;
+    /// Puts whatever the planned meals need beyond the stock on the shopping list, all in
+    /// one transaction. Returns what was missing.
+    async fn shop_for_meal_plan(&self, actor: &Actor) -> Result<Vec<Need>, StoreError>;
+}
+
+#[async_trait]
+pub trait Purchases {
+    /// Records what was paid for an item of the actor's household.
+    async fn record_purchase(&self, purchase: Purchase, actor: &Actor) -> Result<i64, StoreError>;
+    /// Saves the item like [`Store::update`] and records what was paid for it, both or
+    /// neither.
+    async fn buy(&self, record: Item, purchase: Purchase, actor: &Actor)
+    -> Result<i64, StoreError>;
+    /// Every purchase of the household, the latest first.
+    async fn read_purchases(&self, actor: &Actor) -> Result<Vec<Purchase>, StoreError>;
+}
+
+#[async_trait]
+pub trait Carts {
+    /// Ticks the item on the shopping list off as in the cart, or puts it back.
+    async fn set_in_cart(
+        &self,
+        item_id: i64,
+        in_cart: bool,
+        actor: &Actor,
+    ) -> Result<(), StoreError>;
+    /// Ids of the items on the shopping list that are in the cart.
+    async fn read_cart(&self, actor: &Actor) -> Result<Vec<i64>, StoreError>;
+    /// Moves every item in the cart to the stock in a single transaction, see [`CheckOut`].
+    /// Returns how many items were in the cart. No price is entered on a trip, so unlike
+    /// [`Purchases::buy`] this records no [`Purchase`] and the trip stays out of the reports.
+    async fn check_out(&self, actor: &Actor) -> Result<usize, StoreError>;
+}
+
+#[async_trait]
+pub trait Shops {
+    async fn create_shop(&self, name: &str, actor: &Actor) -> Result<i64, StoreError>;
+    /// Deletes the shop together with its aisles and the places of the items in them.
+    async fn delete_shop(&self, id: i64, actor: &Actor) -> Result<(), StoreError>;
+    /// All shops ordered by name, each with its aisles in walking order.
+    async fn read_shops(&self, actor: &Actor) -> Result<Vec<Shop>, StoreError>;
+    /// Adds the aisle after the last one of the shop.
+    async fn create_aisle(
+        &self,
+        shop_id: i64,
+        name: &str,
+        actor: &Actor,
+    ) -> Result<i64, StoreError>;
+    async fn delete_aisle(&self, id: i64, actor: &Actor) -> Result<(), StoreError>;
+    /// Walks the aisles of the shop in the order of `order`, which names each of them once.
+    async fn reorder_aisles(
+        &self,
+        shop_id: i64,
+        order: &[i64],
+        actor: &Actor,
+    ) -> Result<(), StoreError>;
+    /// Puts the item in an aisle of the shop, `None` takes it out of the shop's aisles.
+    async fn place_item(
+        &self,
+        item_id: i64,
+        shop_id: i64,
+        aisle_id: Option<i64>,
+        actor: &Actor,
+    ) -> Result<(), StoreError>;
+    async fn read_placements(&self, shop_id: i64, actor: &Actor) -> Result<Placements, StoreError>;
 }
 
 #[derive(Error, Debug)]
 pub enum StoreError {
-    SqlError(#[from] sqlx::Error),
+    /// Any database failure none of the other variants describes
+    SqlError(sqlx::Error),
+    /// The record does not exist, or belongs to another household
+    NotFound,
+    /// The record was changed by someone else since the version the update is based on, or
+    /// clashes with one that already exists
+    Conflict,
+    /// The record breaks a rule of the schema, like pointing at a location that is gone
+    Validation(String),
+    /// The database can't be reached right now, trying again later may work
+    Unavailable,
 }
 
 impl Display for StoreError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::SqlError(error) => write!(f, "SqlError: {error}"),
+            Self::NotFound => write!(f, "NotFound: the record does not exist"),
+            Self::Conflict => write!(f, "Conflict: the record was changed in the meantime"),
+            Self::Validation(message) => write!(f, "Validation: {message}"),
+            Self::Unavailable => write!(f, "Unavailable: the database can't be reached"),
+        }
+    }
+}
+
+/// Rejects an aisle order that does not name every aisle of the shop exactly once, shared by
+/// the stores.
+pub fn check_aisle_order(aisles: &[Aisle], order: &[i64]) -> Result<(), StoreError> {
+    let mut expected: Vec<i64> = aisles.iter().map(|aisle| aisle.id).collect();
+    let mut given = order.to_vec();
+    expected.sort_unstable();
+    given.sort_unstable();
+    if expected != given {
+        return Err(StoreError::Validation(
+            "an aisle order has to name every aisle of the shop once".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// What checking out the cart does to the stock, shared by the stores.
+#[derive(Default)]
+pub struct CheckOut {
+    /// Stock items as read, each with the items of the cart folded into it
+    pub merges: Vec<(Item, Vec<Item>, Item)>,
+    /// Items of the cart that don't duplicate a stock item, moved to the stock as they are
+    pub moves: Vec<(Item, Item)>,
+}
+
+impl CheckOut {
+    /// Folds each item of the cart into the oldest stock item it duplicates, see
+    /// [`duplicate_key`], as long as their units measure the same thing.
+    pub fn plan(cart: Vec<Item>, stock: &[Item]) -> Self {
+        let mut stock: Vec<&Item> = stock.iter().collect();
+        stock.sort_by_key(|item| item.id);
+        let mut folded: Vec<(&Item, Vec<Item>)> = Vec::new();
+        let mut check_out = CheckOut::default();
+        for item in cart {
+            let key = duplicate_key(&item.name);
+            let into = stock.iter().find(|into| {
+                duplicate_key(&into.name) == key && item.measure().convert(into.unit).is_some()
+            });
+            match into {
+                Some(into) => match folded.iter_mut().find(|(target, _)| target.id == into.id) {
+                    Some((_, items)) => items.push(item),
+                    None => folded.push((into, vec![item])),
+                },
+                None => {
+                    let moved = Item {
+                        state: State::STOCK,
+                        ..item.clone()
+                    };
+                    check_out.moves.push((item, moved));
+                }
+            }
+        }
+        for (into, items) in folded {
+            let merged = into
+                .merged_with(&items)
+                .expect("only items measured alike are folded");
+            check_out.merges.push((into.clone(), items, merged));
+        }
+        check_out
+    }
+}
+
+/// Rejects merges that would count an item twice, shared by the stores.
+pub fn check_merge(into: i64, from: &[i64]) -> Result<(), StoreError> {
+    let mut ids = std::collections::HashSet::from([into]);
+    if from.is_empty() || !from.iter().all(|id| ids.insert(*id)) {
+        return Err(StoreError::Validation(
+            "a merge needs distinct items to fold in".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+impl From<sqlx::Error> for StoreError {
+    fn from(error: sqlx::Error) -> Self {
+        use sqlx::error::ErrorKind;
+
+        match error {
+            sqlx::Error::RowNotFound => Self::NotFound,
+            sqlx::Error::Database(ref database) => match database.kind() {
+                ErrorKind::UniqueViolation => Self::Conflict,
+                ErrorKind::ForeignKeyViolation
+                | ErrorKind::NotNullViolation
+                | ErrorKind::CheckViolation => Self::Validation(database.message().to_string()),
+                _ => Self::SqlError(error),
+            },
+            sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::WorkerCrashed => Self::Unavailable,
+            error => Self::SqlError(error),
         }
     }
 }
@@ -34,69 +320,751 @@ pub struct SqliteItemStore {
 
 #[async_trait]
 impl Store<Item> for SqliteItemStore {
-    async fn create(&self, record: Item) -> Result<i64, StoreError> {
-        let state = record.state as i64;
-        // Insert the task, then obtain the ID of this row
-        let query = sqlx::query!(
-            r#"INSERT INTO item ( name, quantity, state ) VALUES (?1, ?2, ?3)"#,
-            record.name,
-            record.quantity,
-            state,
-        );
-        let id = query.execute(&self.pool).await?.last_insert_rowid();
+    async fn create(&self, record: Item, actor: &Actor) -> Result<i64, StoreError> {
+        let mut tx = self.pool.begin().await?;
+        let id = insert_item(&mut tx, &record, actor).await?;
+        record_event(&mut tx, id, EventKind::Created, None, Some(&record), actor).await?;
+        tx.commit().await?;
 
         Ok(id)
     }
 
-    async fn delete(&self, id: i64) -> Result<(), StoreError> {
-        // Insert the task, then obtain the ID of this row
+    async fn create_many(&self, records: Vec<Item>, actor: &Actor) -> Result<Vec<i64>, StoreError> {
+        let mut tx = self.pool.begin().await?;
+        let mut ids = Vec::with_capacity(records.len());
+        for record in &records {
+            let id = insert_item(&mut tx, record, actor).await?;
+            record_event(&mut tx, id, EventKind::Created, None, Some(record), actor).await?;
+            ids.push(id);
+        }
+        tx.commit().await?;
+
+        Ok(ids)
+    }
+
+    async fn delete(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin().await?;
+        let Some(previous) = fetch_item(&mut *tx, id, actor).await? else {
+            return Err(StoreError::NotFound);
+        };
+        // Recipes keep the ingredient under the name the item had last
+        sqlx::query!(
+            r#"UPDATE ingredient SET name = ?1 WHERE item_id = ?2"#,
+            previous.name,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
         sqlx::query!(r#"DELETE FROM item WHERE id = ?1"#, id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
+        record_event(
+            &mut tx,
+            id,
+            EventKind::Deleted,
+            Some(&previous),
+            None,
+            actor,
+        )
+        .await?;
+        tx.commit().await?;
 
         Ok(())
     }
 
-    async fn update(&self, record: Item) -> Result<(), StoreError> {
-        let state = record.state as i64;
-        // Insert the task, then obtain the ID of this row
-        sqlx::query!(
-            r#"UPDATE item SET name = ?1, quantity = ?2, state = ?3 WHERE id = ?4"#,
-            record.name,
-            record.quantity,
+    async fn update(&self, record: Item, actor: &Actor) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin().await?;
+        save_item(&mut tx, &record, actor).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn consume(&self, id: i64, amount: f64, actor: &Actor) -> Result<(), StoreError> {
+        self.adjust_quantity(id, -amount, actor).await
+    }
+
+    async fn restock(&self, id: i64, amount: f64, actor: &Actor) -> Result<(), StoreError> {
+        self.adjust_quantity(id, amount, actor).await
+    }
+
+    async fn merge(&self, into: i64, from: &[i64], actor: &Actor) -> Result<(), StoreError> {
+        check_merge(into, from)?;
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let mut items = Vec::with_capacity(from.len() + 1);
+        for &id in std::iter::once(&into).chain(from) {
+            let Some(item) = fetch_item(&mut *tx, id, actor).await? else {
+                tx.rollback().await?;
+                return Err(StoreError::NotFound);
+            };
+            items.push(item);
+        }
+        let (target, others) = items.split_first().expect("the target is read first");
+        let Some(merged) = target.merged_with(others) else {
+            tx.rollback().await?;
+            return Err(StoreError::Validation(
+                "the items are measured in different kinds of units".to_string(),
+            ));
+        };
+
+        update_item(&mut tx, &merged, actor).await?;
+        record_event(
+            &mut tx,
+            into,
+            EventKind::Updated,
+            Some(target),
+            Some(&merged),
+            actor,
+        )
+        .await?;
+        for other in others {
+            fold_item(&mut tx, into, other, actor).await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn read(&self, id: i64, actor: &Actor) -> Result<Item, StoreError> {
+        let record = fetch_item(&self.pool, id, actor)
+            .await?
+            .ok_or(StoreError::NotFound)?;
+
+        Ok(record)
+    }
+
+    async fn read_many_from_state(
+        &self,
+        state: State,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        let state = state.id();
+        let household = actor.household_id();
+        let records = sqlx::query_as!(
+            Item,
+            r#"SELECT id AS "id!", name, quantity, unit, state, location_id, expires_on, par_quantity, version FROM item WHERE state = ?1 AND household_id = ?2"#,
+            state,
+            household
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn read_many_from_state_in_location(
+        &self,
+        state: State,
+        location: i64,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        let state = state.id();
+        let household = actor.household_id();
+        let records = sqlx::query_as!(
+            Item,
+            r#"WITH RECURSIVE scope (id) AS (
+                SELECT id FROM location WHERE id = ?2 AND household_id = ?3
+                UNION
+                SELECT location.id FROM location JOIN scope ON location.parent_id = scope.id
+            )
+            SELECT id AS "id!", name, quantity, unit, state, location_id, expires_on, par_quantity, version
+            FROM item
+            WHERE state = ?1 AND household_id = ?3 AND location_id IN (SELECT id FROM scope)"#,
+            state,
+            location,
+            household
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn read_page(
+        &self,
+        state: State,
+        request: &PageRequest,
+        actor: &Actor,
+    ) -> Result<Page<Item>, StoreError> {
+        let state = state.id();
+        let sort = request.sort.to_string();
+        let household = actor.household_id();
+        let (after_text, after_number, after_id) = match &request.after {
+            Some(cursor) => (
+                Some(cursor.text.as_str()),
+                Some(cursor.number),
+                Some(cursor.id),
+            ),
+            None => (None, None, None),
+        };
+        // One more than a page tells whether another page follows
+        let limit = request.limit.saturating_add(1);
+        let rows = sqlx::query!(
+            r#"WITH RECURSIVE scope (id) AS (
+                SELECT id FROM location WHERE id = ?2 AND household_id = ?4
+                UNION
+                SELECT location.id FROM location JOIN scope ON location.parent_id = scope.id
+            )
+            SELECT id AS "id!", name, quantity, unit, state, location_id, expires_on, par_quantity, version,
+                sort_text AS "sort_text!: String", sort_number AS "sort_number!: f64"
+            FROM (
+                SELECT *,
+                    COALESCE(CASE ?3 WHEN 'name' THEN lower(name) END, '') AS sort_text,
+                    COALESCE(CASE ?3
+                        WHEN 'quantity' THEN quantity * CASE unit WHEN 'kg' THEN 1000 WHEN 'l' THEN 1000 ELSE 1 END
+                        WHEN 'updated' THEN -julianday(updated_at)
+                        WHEN 'expiry' THEN julianday(COALESCE(expires_on, '9999-12-31'))
+                    END, 0.0) AS sort_number
+                FROM item
+                WHERE state = ?1 AND household_id = ?4
+                AND (?2 IS NULL OR location_id IN (SELECT id FROM scope))
+            )
+            WHERE ?5 IS NULL OR (sort_text, sort_number, id) > (?6, ?7, ?5)
+            ORDER BY sort_text, sort_number, id
+            LIMIT ?8"#,
+            state,
+            request.location,
+            sort,
+            household,
+            after_id,
+            after_text,
+            after_number,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let has_more = rows.len() as i64 > request.limit;
+        let mut next = None;
+        let mut records = Vec::with_capacity(rows.len());
+        for row in rows.into_iter().take(request.limit as usize) {
+            next = Some(Cursor {
+                text: row.sort_text,
+                number: row.sort_number,
+                id: row.id,
+            });
+            records.push(Item {
+                id: row.id,
+                name: row.name,
+                quantity: row.quantity,
+                unit: row.unit.into(),
+                state: row.state.into(),
+                location_id: row.location_id,
+                expires_on: row.expires_on,
+                par_quantity: row.par_quantity,
+                version: row.version,
+            });
+        }
+        let next = next.filter(|_| has_more);
+
+        Ok(Page { records, next })
+    }
+
+    async fn read_expiring_until(
+        &self,
+        until: NaiveDate,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        let state = State::STOCK.id();
+        let household = actor.household_id();
+        let records = sqlx::query_as!(
+            Item,
+            r#"SELECT id AS "id!", name, quantity, unit, state, location_id, expires_on, par_quantity, version
+            FROM item
+            WHERE state = ?1 AND expires_on <= ?2 AND household_id = ?3
+            ORDER BY expires_on, name"#,
             state,
-            record.id
+            until,
+            household
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        state: State,
+        actor: &Actor,
+    ) -> Result<Vec<Item>, StoreError> {
+        let query = SearchQuery::new(query);
+        if query.is_empty() {
+            return self.read_many_from_state(state, actor).await;
+        }
+
+        // The index narrows down the candidates, scoring them decides what matches
+        let candidates = match query.fts_expression() {
+            Some(expression) => {
+                let state = state.id();
+                let household = actor.household_id();
+                sqlx::query_as!(
+                    Item,
+                    r#"SELECT item.id AS "id!", item.name, item.quantity, item.unit, item.state, item.location_id, item.expires_on, item.par_quantity, item.version
+                    FROM item_search JOIN item ON item.id = item_search.rowid
+                    WHERE item_search MATCH ?1 AND item.state = ?2 AND item.household_id = ?3"#,
+                    expression,
+                    state,
+                    household
+                )
+                .fetch_all(&self.pool)
+                .await?
+            }
+            None => self.read_many_from_state(state, actor).await?,
+        };
+
+        let mut scored: Vec<(f64, Item)> = candidates
+            .into_iter()
+            .filter_map(|item| query.score(&item.name).map(|score| (score, item)))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        Ok(scored.into_iter().map(|(_, item)| item).collect())
+    }
+}
+
+#[async_trait]
+impl Locations for SqliteItemStore {
+    async fn create_location(&self, location: Location, actor: &Actor) -> Result<i64, StoreError> {
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"INSERT INTO location ( name, parent_id, household_id )
+            SELECT ?1, ?2, ?3
+            WHERE ?2 IS NULL OR EXISTS (SELECT 1 FROM location WHERE id = ?2 AND household_id = ?3)"#,
+            location.name,
+            location.parent_id,
+            household,
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn delete_location(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"DELETE FROM location WHERE id = ?1 AND household_id = ?2"#,
+            id,
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn read_locations(&self, actor: &Actor) -> Result<Vec<Location>, StoreError> {
+        let household = actor.household_id();
+        let records = sqlx::query_as!(
+            Location,
+            r#"SELECT id, name, parent_id FROM location WHERE household_id = ?1 ORDER BY name"#,
+            household
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+}
+
+#[async_trait]
+impl History for SqliteItemStore {
+    async fn read_history(
+        &self,
+        item_id: i64,
+        actor: &Actor,
+    ) -> Result<Vec<ItemEvent>, StoreError> {
+        let household = actor.household_id();
+        let records = sqlx::query_as!(
+            ItemEvent,
+            r#"SELECT id AS "id!", item_id, kind, old_value, new_value, actor, created_at
+            FROM item_event
+            WHERE item_id = ?1 AND household_id = ?2
+            ORDER BY id DESC"#,
+            item_id,
+            household
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+}
+
+#[async_trait]
+impl Users for SqliteItemStore {
+    async fn create_user(
+        &self,
+        username: &str,
+        password_hash: &str,
+        household: &str,
+    ) -> Result<i64, StoreError> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            r#"INSERT INTO household ( name ) VALUES (?1) ON CONFLICT (name) DO NOTHING"#,
+            household
+        )
+        .execute(&mut *tx)
+        .await?;
+        let id = sqlx::query!(
+            r#"INSERT INTO user ( username, password_hash, household_id )
+            SELECT ?1, ?2, id FROM household WHERE name = ?3"#,
+            username,
+            password_hash,
+            household
+        )
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
+    async fn read_user_by_name(&self, username: &str) -> Result<Option<User>, StoreError> {
+        let record = sqlx::query_as!(
+            User,
+            r#"SELECT id AS "id!", username, password_hash, household_id FROM user WHERE username = ?1"#,
+            username
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record)
+    }
+
+    async fn create_session(
+        &self,
+        token: &str,
+        user_id: i64,
+        expires_at: NaiveDateTime,
+    ) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin().await?;
+        // Piggyback the cleanup of expired sessions on every sign in
+        sqlx::query!(r#"DELETE FROM session WHERE expires_at <= CURRENT_TIMESTAMP"#)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query!(
+            r#"INSERT INTO session ( token, user_id, expires_at ) VALUES (?1, ?2, ?3)"#,
+            token,
+            user_id,
+            expires_at
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn read_session_user(&self, token: &str) -> Result<Option<User>, StoreError> {
+        let record = sqlx::query_as!(
+            User,
+            r#"SELECT user.id AS "id!", user.username, user.password_hash, user.household_id
+            FROM session JOIN user ON user.id = session.user_id
+            WHERE session.token = ?1 AND session.expires_at > CURRENT_TIMESTAMP"#,
+            token
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(record)
+    }
+
+    async fn delete_session(&self, token: &str) -> Result<(), StoreError> {
+        sqlx::query!(r#"DELETE FROM session WHERE token = ?1"#, token)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Recipes for SqliteItemStore {
+    async fn create_recipe(&self, name: &str, actor: &Actor) -> Result<i64, StoreError> {
+        let household = actor.household_id();
+        let id = sqlx::query!(
+            r#"INSERT INTO recipe ( name, household_id ) VALUES (?1, ?2)"#,
+            name,
+            household
         )
         .execute(&self.pool)
         .await?
-        .rows_affected();
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    async fn update_recipe(&self, id: i64, name: &str, actor: &Actor) -> Result<(), StoreError> {
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"UPDATE recipe SET name = ?1 WHERE id = ?2 AND household_id = ?3"#,
+            name,
+            id,
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
 
         Ok(())
     }
 
-    async fn read(&self, id: i64) -> Result<Item, StoreError> {
-        // Insert the task, then obtain the ID of this row
-        let record = sqlx::query!(
-            r#"SELECT id, name, quantity, state FROM item WHERE id = ?1"#,
-            id
+    async fn delete_recipe(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"DELETE FROM recipe WHERE id = ?1 AND household_id = ?2"#,
+            id,
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn read_recipe(&self, id: i64, actor: &Actor) -> Result<Recipe, StoreError> {
+        let household = actor.household_id();
+        let mut recipe = sqlx::query!(
+            r#"SELECT id, name FROM recipe WHERE id = ?1 AND household_id = ?2"#,
+            id,
+            household
         )
         .fetch_one(&self.pool)
+        .await
+        .map(|record| Recipe::new(record.id, record.name))?;
+        recipe.ingredients = sqlx::query_as!(
+            Ingredient,
+            r#"SELECT ingredient.id AS "id!", ingredient.recipe_id, ingredient.item_id, COALESCE(item.name, ingredient.name) AS "name!: String", ingredient.quantity, ingredient.unit
+            FROM ingredient LEFT JOIN item ON item.id = ingredient.item_id
+            WHERE ingredient.recipe_id = ?1
+            ORDER BY ingredient.id"#,
+            id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(recipe)
+    }
+
+    async fn read_recipes(&self, actor: &Actor) -> Result<Vec<Recipe>, StoreError> {
+        let household = actor.household_id();
+        let mut recipes: Vec<Recipe> = sqlx::query!(
+            r#"SELECT id AS "id!", name FROM recipe WHERE household_id = ?1 ORDER BY name"#,
+            household
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|record| Recipe::new(record.id, record.name))
+        .collect();
+        let ingredients = sqlx::query_as!(
+            Ingredient,
+            r#"SELECT ingredient.id AS "id!", ingredient.recipe_id, ingredient.item_id, COALESCE(item.name, ingredient.name) AS "name!: String", ingredient.quantity, ingredient.unit
+            FROM ingredient
+            LEFT JOIN item ON item.id = ingredient.item_id
+            JOIN recipe ON recipe.id = ingredient.recipe_id
+            WHERE recipe.household_id = ?1
+            ORDER BY ingredient.id"#,
+            household
+        )
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(Item {
+        for ingredient in ingredients {
+            if let Some(recipe) = recipes
+                .iter_mut()
+                .find(|recipe| recipe.id == ingredient.recipe_id)
+            {
+                recipe.ingredients.push(ingredient);
+            }
+        }
+
+        Ok(recipes)
+    }
+
+    async fn create_ingredient(
+        &self,
+        ingredient: Ingredient,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        let unit = ingredient.unit.to_string();
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"INSERT INTO ingredient ( recipe_id, item_id, name, quantity, unit )
+            SELECT ?1, item.id, item.name, ?3, ?4 FROM item
+            WHERE item.id = ?2 AND item.household_id = ?5
+            AND EXISTS (SELECT 1 FROM recipe WHERE id = ?1 AND household_id = ?5)"#,
+            ingredient.recipe_id,
+            ingredient.item_id,
+            ingredient.quantity,
+            unit,
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn delete_ingredient(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"DELETE FROM ingredient
+            WHERE id = ?1 AND recipe_id IN (SELECT id FROM recipe WHERE household_id = ?2)"#,
             id,
-            name: record.name,
-            quantity: record.quantity,
-            state: record.state.into(),
-        })
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
     }
+}
 
-    async fn read_many_from_state(&self, state: State) -> Result<Vec<Item>, StoreError> {
-        let state = state as i64;
+#[async_trait]
+impl MealPlans for SqliteItemStore {
+    async fn read_meal_plan(&self, actor: &Actor) -> Result<Vec<PlannedMeal>, StoreError> {
+        let household = actor.household_id();
         let records = sqlx::query_as!(
+            PlannedMeal,
+            r#"SELECT meal_plan.id AS "id!", meal_plan.weekday, meal_plan.recipe_id, recipe.name AS recipe_name
+            FROM meal_plan JOIN recipe ON recipe.id = meal_plan.recipe_id
+            WHERE meal_plan.household_id = ?1
+            ORDER BY meal_plan.weekday, meal_plan.id"#,
+            household
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records)
+    }
+
+    async fn plan_meal(
+        &self,
+        weekday: i64,
+        recipe_id: i64,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"INSERT INTO meal_plan ( weekday, recipe_id, household_id )
+            SELECT ?1, ?2, ?3
+            WHERE EXISTS (SELECT 1 FROM recipe WHERE id = ?2 AND household_id = ?3)"#,
+            weekday,
+            recipe_id,
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn delete_planned_meal(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"DELETE FROM meal_plan WHERE id = ?1 AND household_id = ?2"#,
+            id,
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn shop_for_meal_plan(&self, actor: &Actor) -> Result<Vec<Need>, StoreError> {
+        // Written right after reading, so take the write lock before the reads
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let household = actor.household_id();
+        // A recipe planned twice brings its ingredients twice
+        let ingredients = sqlx::query_as!(
+            Ingredient,
+            r#"SELECT ingredient.id AS "id!", ingredient.recipe_id, ingredient.item_id, COALESCE(item.name, ingredient.name) AS "name!: String", ingredient.quantity, ingredient.unit
+            FROM meal_plan
+            JOIN ingredient ON ingredient.recipe_id = meal_plan.recipe_id
+            LEFT JOIN item ON item.id = ingredient.item_id
+            WHERE meal_plan.household_id = ?1"#,
+            household
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let state = State::STOCK.id();
+        let stock = sqlx::query_as!(
             Item,
-            r#"SELECT id, name, quantity, state FROM item WHERE state = ?1"#,
-            state
+            r#"SELECT id AS "id!", name, quantity, unit, state, location_id, expires_on, par_quantity, version FROM item WHERE state = ?1 AND household_id = ?2"#,
+            state,
+            household
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let needs = crate::meal_plan::needs(&ingredients, &stock);
+        for need in &needs {
+            add_to_shopping(&mut tx, &need.name, None, need.missing, None, actor).await?;
+        }
+        tx.commit().await?;
+
+        Ok(needs)
+    }
+}
+
+#[async_trait]
+impl Purchases for SqliteItemStore {
+    async fn record_purchase(&self, purchase: Purchase, actor: &Actor) -> Result<i64, StoreError> {
+        let mut conn = self.pool.acquire().await?;
+        insert_purchase(&mut conn, &purchase, actor).await
+    }
+
+    async fn buy(
+        &self,
+        record: Item,
+        purchase: Purchase,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        let mut tx = self.pool.begin().await?;
+        save_item(&mut tx, &record, actor).await?;
+        let id = insert_purchase(&mut tx, &purchase, actor).await?;
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
+    async fn read_purchases(&self, actor: &Actor) -> Result<Vec<Purchase>, StoreError> {
+        let household = actor.household_id();
+        let records = sqlx::query_as!(
+            Purchase,
+            r#"SELECT id AS "id!", item_id, name, quantity, unit, price_cents AS price, shop, purchased_on
+            FROM purchase WHERE household_id = ?1
+            ORDER BY purchased_on DESC, id DESC"#,
+            household
         )
         .fetch_all(&self.pool)
         .await?;
@@ -105,7 +1073,675 @@ impl Store<Item> for SqliteItemStore {
     }
 }
 
+#[async_trait]
+impl Carts for SqliteItemStore {
+    async fn set_in_cart(
+        &self,
+        item_id: i64,
+        in_cart: bool,
+        actor: &Actor,
+    ) -> Result<(), StoreError> {
+        let state = State::SHOPPING.id();
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"UPDATE item SET in_cart = ?1 WHERE id = ?2 AND state = ?3 AND household_id = ?4"#,
+            in_cart,
+            item_id,
+            state,
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn read_cart(&self, actor: &Actor) -> Result<Vec<i64>, StoreError> {
+        let state = State::SHOPPING.id();
+        let household = actor.household_id();
+        let ids = sqlx::query_scalar!(
+            r#"SELECT id AS "id!" FROM item
+            WHERE in_cart AND state = ?1 AND household_id = ?2 ORDER BY id"#,
+            state,
+            household
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    async fn check_out(&self, actor: &Actor) -> Result<usize, StoreError> {
+        let shopping = State::SHOPPING.id();
+        let stock = State::STOCK.id();
+        let household = actor.household_id();
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let cart = sqlx::query_as!(
+            Item,
+            r#"SELECT id AS "id!", name, quantity, unit, state, location_id, expires_on, par_quantity, version
+            FROM item WHERE in_cart AND state = ?1 AND household_id = ?2 ORDER BY id"#,
+            shopping,
+            household
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let stocked = sqlx::query_as!(
+            Item,
+            r#"SELECT id AS "id!", name, quantity, unit, state, location_id, expires_on, par_quantity, version
+            FROM item WHERE state = ?1 AND household_id = ?2"#,
+            stock,
+            household
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let count = cart.len();
+        let check_out = CheckOut::plan(cart, &stocked);
+
+        for (into, items, merged) in &check_out.merges {
+            update_item(&mut tx, merged, actor).await?;
+            record_event(
+                &mut tx,
+                into.id,
+                EventKind::Updated,
+                Some(into),
+                Some(merged),
+                actor,
+            )
+            .await?;
+            for item in items {
+                fold_item(&mut tx, into.id, item, actor).await?;
+            }
+        }
+        for (item, moved) in &check_out.moves {
+            update_item(&mut tx, moved, actor).await?;
+            record_event(
+                &mut tx,
+                item.id,
+                EventKind::Updated,
+                Some(item),
+                Some(moved),
+                actor,
+            )
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(count)
+    }
+}
+
+#[async_trait]
+impl Shops for SqliteItemStore {
+    async fn create_shop(&self, name: &str, actor: &Actor) -> Result<i64, StoreError> {
+        let household = actor.household_id();
+        let id = sqlx::query!(
+            r#"INSERT INTO shop ( name, household_id ) VALUES (?1, ?2)"#,
+            name,
+            household
+        )
+        .execute(&self.pool)
+        .await?
+        .last_insert_rowid();
+
+        Ok(id)
+    }
+
+    async fn delete_shop(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"DELETE FROM shop WHERE id = ?1 AND household_id = ?2"#,
+            id,
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn read_shops(&self, actor: &Actor) -> Result<Vec<Shop>, StoreError> {
+        let household = actor.household_id();
+        let shops = sqlx::query!(
+            r#"SELECT id AS "id!", name FROM shop WHERE household_id = ?1 ORDER BY name, id"#,
+            household
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let aisles = sqlx::query_as!(
+            Aisle,
+            r#"SELECT aisle.id AS "id!", aisle.shop_id, aisle.name
+            FROM aisle JOIN shop ON shop.id = aisle.shop_id
+            WHERE shop.household_id = ?1
+            ORDER BY aisle.position, aisle.id"#,
+            household
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(shops
+            .into_iter()
+            .map(|shop| Shop {
+                id: shop.id,
+                name: shop.name,
+                aisles: aisles
+                    .iter()
+                    .filter(|aisle| aisle.shop_id == shop.id)
+                    .cloned()
+                    .collect(),
+            })
+            .collect())
+    }
+
+    async fn create_aisle(
+        &self,
+        shop_id: i64,
+        name: &str,
+        actor: &Actor,
+    ) -> Result<i64, StoreError> {
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"INSERT INTO aisle ( shop_id, name, position )
+            SELECT ?1, ?2, COALESCE((SELECT MAX(position) FROM aisle WHERE shop_id = ?1), -1) + 1
+            WHERE EXISTS (SELECT 1 FROM shop WHERE id = ?1 AND household_id = ?3)"#,
+            shop_id,
+            name,
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(result.last_insert_rowid())
+    }
+
+    async fn delete_aisle(&self, id: i64, actor: &Actor) -> Result<(), StoreError> {
+        let household = actor.household_id();
+        let result = sqlx::query!(
+            r#"DELETE FROM aisle
+            WHERE id = ?1 AND shop_id IN (SELECT id FROM shop WHERE household_id = ?2)"#,
+            id,
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn reorder_aisles(
+        &self,
+        shop_id: i64,
+        order: &[i64],
+        actor: &Actor,
+    ) -> Result<(), StoreError> {
+        // Written right after reading, so take the write lock before the reads
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let household = actor.household_id();
+        let shop = sqlx::query_scalar!(
+            r#"SELECT id AS "id!" FROM shop WHERE id = ?1 AND household_id = ?2"#,
+            shop_id,
+            household
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if shop.is_none() {
+            tx.rollback().await?;
+            return Err(StoreError::NotFound);
+        }
+        let aisles = sqlx::query_as!(
+            Aisle,
+            r#"SELECT id AS "id!", shop_id, name FROM aisle WHERE shop_id = ?1"#,
+            shop_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        if let Err(err) = check_aisle_order(&aisles, order) {
+            tx.rollback().await?;
+            return Err(err);
+        }
+
+        for (position, id) in (0_i64..).zip(order) {
+            sqlx::query!(
+                r#"UPDATE aisle SET position = ?1 WHERE id = ?2"#,
+                position,
+                id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn place_item(
+        &self,
+        item_id: i64,
+        shop_id: i64,
+        aisle_id: Option<i64>,
+        actor: &Actor,
+    ) -> Result<(), StoreError> {
+        let household = actor.household_id();
+        let Some(aisle_id) = aisle_id else {
+            sqlx::query!(
+                r#"DELETE FROM item_aisle
+                WHERE item_id = ?1 AND shop_id = ?2
+                AND shop_id IN (SELECT id FROM shop WHERE household_id = ?3)"#,
+                item_id,
+                shop_id,
+                household
+            )
+            .execute(&self.pool)
+            .await?;
+            return Ok(());
+        };
+        let result = sqlx::query!(
+            r#"INSERT INTO item_aisle ( item_id, shop_id, aisle_id )
+            SELECT ?1, ?2, ?3
+            WHERE EXISTS (SELECT 1 FROM item WHERE id = ?1 AND household_id = ?4)
+            AND EXISTS (
+                SELECT 1 FROM aisle JOIN shop ON shop.id = aisle.shop_id
+                WHERE aisle.id = ?3 AND aisle.shop_id = ?2 AND shop.household_id = ?4
+            )
+            ON CONFLICT (item_id, shop_id) DO UPDATE SET aisle_id = excluded.aisle_id"#,
+            item_id,
+            shop_id,
+            aisle_id,
+            household
+        )
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(StoreError::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn read_placements(&self, shop_id: i64, actor: &Actor) -> Result<Placements, StoreError> {
+        let household = actor.household_id();
+        let records = sqlx::query!(
+            r#"SELECT item_aisle.item_id, item_aisle.aisle_id
+            FROM item_aisle JOIN shop ON shop.id = item_aisle.shop_id
+            WHERE item_aisle.shop_id = ?1 AND shop.household_id = ?2"#,
+            shop_id,
+            household
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(records
+            .into_iter()
+            .map(|record| (record.item_id, record.aisle_id))
+            .collect())
+    }
+}
+
+async fn fetch_item<'e>(
+    executor: impl SqliteExecutor<'e>,
+    id: i64,
+    actor: &Actor,
+) -> Result<Option<Item>, StoreError> {
+    let household = actor.household_id();
+    let record = sqlx::query_as!(
+        Item,
+        r#"SELECT id, name, quantity, unit, state, location_id, expires_on, par_quantity, version FROM item WHERE id = ?1 AND household_id = ?2"#,
+        id,
+        household
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(record)
+}
+
+async fn insert_item(
+    conn: &mut SqliteConnection,
+    record: &Item,
+    actor: &Actor,
+) -> Result<i64, StoreError> {
+    let state = record.state.id();
+    let unit = record.unit.to_string();
+    let household = actor.household_id();
+    // Insert the task unless its location belongs to another household, then obtain the ID
+    // of this row
+    let result = sqlx::query!(
+        r#"INSERT INTO item ( name, quantity, unit, state, location_id, expires_on, par_quantity, household_id )
+        SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8
+        WHERE ?5 IS NULL OR EXISTS (SELECT 1 FROM location WHERE id = ?5 AND household_id = ?8)"#,
+        record.name,
+        record.quantity,
+        unit,
+        state,
+        record.location_id,
+        record.expires_on,
+        record.par_quantity,
+        household,
+    )
+    .execute(conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(StoreError::NotFound);
+    }
+
+    Ok(result.last_insert_rowid())
+}
+
+/// Writes the item unless its row moved past `record.version`, returns whether it did. An
+/// item moved to another state leaves the cart. A location of another household is not
+/// found.
+async fn update_item(
+    conn: &mut SqliteConnection,
+    record: &Item,
+    actor: &Actor,
+) -> Result<bool, StoreError> {
+    let state = record.state.id();
+    let unit = record.unit.to_string();
+    let household = actor.household_id();
+    let result = sqlx::query!(
+        r#"UPDATE item SET name = ?1, quantity = ?2, unit = ?3, state = ?4, location_id = ?5, expires_on = ?6, par_quantity = ?7, in_cart = in_cart AND state = ?4, version = version + 1
+        WHERE id = ?8 AND version = ?9
+        AND (?5 IS NULL OR EXISTS (SELECT 1 FROM location WHERE id = ?5 AND household_id = ?10))"#,
+        record.name,
+        record.quantity,
+        unit,
+        state,
+        record.location_id,
+        record.expires_on,
+        record.par_quantity,
+        record.id,
+        record.version,
+        household
+    )
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() > 0 {
+        return Ok(true);
+    }
+
+    // Nothing written, either the version moved on or the location is not the household's
+    match record.location_id {
+        Some(location) if !has_location(conn, location, actor).await? => Err(StoreError::NotFound),
+        _ => Ok(false),
+    }
+}
+
+async fn has_location(
+    conn: &mut SqliteConnection,
+    id: i64,
+    actor: &Actor,
+) -> Result<bool, StoreError> {
+    let household = actor.household_id();
+    let found = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM location WHERE id = ?1 AND household_id = ?2) AS "found!: bool""#,
+        id,
+        household
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(found)
+}
+
+/// Writes an update of the item with its event and restocking, see [`Store::update`].
+async fn save_item(
+    conn: &mut SqliteConnection,
+    record: &Item,
+    actor: &Actor,
+) -> Result<(), StoreError> {
+    let Some(previous) = fetch_item(&mut *conn, record.id, actor).await? else {
+        return Err(StoreError::NotFound);
+    };
+
+    // Checking the version read here is not enough on its own, the guarded update
+    // also catches a write landing in between
+    if previous.version != record.version || !update_item(conn, record, actor).await? {
+        return Err(StoreError::Conflict);
+    }
+    record_event(
+        conn,
+        record.id,
+        EventKind::Updated,
+        Some(&previous),
+        Some(record),
+        actor,
+    )
+    .await?;
+    restock_shortfall(conn, &previous, record, actor).await
+}
+
+async fn insert_purchase(
+    conn: &mut SqliteConnection,
+    purchase: &Purchase,
+    actor: &Actor,
+) -> Result<i64, StoreError> {
+    let household = actor.household_id();
+    let unit = purchase.unit.to_string();
+    let result = sqlx::query!(
+        r#"INSERT INTO purchase ( item_id, name, quantity, unit, price_cents, shop, purchased_on, household_id )
+        SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8
+        WHERE EXISTS (SELECT 1 FROM item WHERE id = ?1 AND household_id = ?8)"#,
+        purchase.item_id,
+        purchase.name,
+        purchase.quantity,
+        unit,
+        purchase.price.0,
+        purchase.shop,
+        purchase.purchased_on,
+        household
+    )
+    .execute(conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(StoreError::NotFound);
+    }
+
+    Ok(result.last_insert_rowid())
+}
+
+/// Deletes `other` once folded into the item `into`, which takes over its ingredients and
+/// its aisles in shops where it has none of its own.
+async fn fold_item(
+    conn: &mut SqliteConnection,
+    into: i64,
+    other: &Item,
+    actor: &Actor,
+) -> Result<(), StoreError> {
+    sqlx::query!(
+        r#"UPDATE ingredient SET item_id = ?1 WHERE item_id = ?2"#,
+        into,
+        other.id
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(
+        r#"INSERT INTO item_aisle ( item_id, shop_id, aisle_id )
+        SELECT ?1, shop_id, aisle_id FROM item_aisle WHERE item_id = ?2
+        ON CONFLICT DO NOTHING"#,
+        into,
+        other.id
+    )
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query!(r#"DELETE FROM item WHERE id = ?1"#, other.id)
+        .execute(&mut *conn)
+        .await?;
+    record_event(conn, other.id, EventKind::Deleted, Some(other), None, actor).await
+}
+
+async fn record_event(
+    conn: &mut SqliteConnection,
+    item_id: i64,
+    kind: EventKind,
+    old: Option<&Item>,
+    new: Option<&Item>,
+    actor: &Actor,
+) -> Result<(), StoreError> {
+    let kind = kind.to_string();
+    let old = old.map(Item::to_string);
+    let new = new.map(Item::to_string);
+    let name = actor.name();
+    let household = actor.household_id();
+    sqlx::query!(
+        r#"INSERT INTO item_event ( item_id, kind, old_value, new_value, actor, household_id ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)"#,
+        item_id,
+        kind,
+        old,
+        new,
+        name,
+        household,
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Puts the shortfall on the shopping list when an update dropped the quantity below par.
+async fn restock_shortfall(
+    conn: &mut SqliteConnection,
+    previous: &Item,
+    record: &Item,
+    actor: &Actor,
+) -> Result<(), StoreError> {
+    if let Some(shortfall) = record.shortfall_since(previous) {
+        add_to_shopping(
+            conn,
+            &record.name,
+            record.location_id,
+            shortfall,
+            Some(record.id),
+            actor,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Makes sure the shopping list holds at least `shortfall` of `name`, topping up an entry of
+/// that name already on the list instead of adding a second one. A new entry is placed in
+/// the aisles of the stock item `aisles_of`.
+async fn add_to_shopping(
+    conn: &mut SqliteConnection,
+    name: &str,
+    location_id: Option<i64>,
+    shortfall: Quantity,
+    aisles_of: Option<i64>,
+    actor: &Actor,
+) -> Result<(), StoreError> {
+    let state = State::SHOPPING.id();
+    let household = actor.household_id();
+    let existing = sqlx::query_as!(
+        Item,
+        r#"SELECT id AS "id!", name, quantity, unit, state, location_id, expires_on, par_quantity, version
+        FROM item
+        WHERE state = ?1 AND lower(trim(name)) = lower(trim(?2)) AND household_id = ?3"#,
+        state,
+        name,
+        household
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for previous in existing {
+        let Some(needed) = shortfall.convert(previous.unit) else {
+            continue;
+        };
+        if previous.quantity < needed.amount {
+            let record = Item {
+                quantity: needed.amount,
+                ..previous.clone()
+            };
+            update_item(conn, &record, actor).await?;
+            record_event(
+                conn,
+                record.id,
+                EventKind::Updated,
+                Some(&previous),
+                Some(&record),
+                actor,
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+
+    let record = Item::new(
+        0,
+        name.to_string(),
+        shortfall.amount,
+        shortfall.unit,
+        State::SHOPPING,
+    )
+    .with_location(location_id);
+    let id = insert_item(conn, &record, actor).await?;
+    if let Some(aisles_of) = aisles_of {
+        sqlx::query!(
+            r#"INSERT INTO item_aisle ( item_id, shop_id, aisle_id )
+            SELECT ?1, shop_id, aisle_id FROM item_aisle WHERE item_id = ?2"#,
+            id,
+            aisles_of
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    record_event(conn, id, EventKind::Created, None, Some(&record), actor).await?;
+
+    Ok(())
+}
+
 impl SqliteItemStore {
+    // The relative update keeps concurrent changes from overwriting each other. Taking the
+    // write lock up front queues concurrent adjustments behind the busy timeout, a deferred
+    // transaction reading first would fail with "database is locked" instead.
+    async fn adjust_quantity(&self, id: i64, delta: f64, actor: &Actor) -> Result<(), StoreError> {
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+        let Some(previous) = fetch_item(&mut *tx, id, actor).await? else {
+            // Dropping the transaction only rolls back once the connection is used again,
+            // until then it would keep the write lock `BEGIN IMMEDIATE` took
+            tx.rollback().await?;
+            return Err(StoreError::NotFound);
+        };
+
+        let household = actor.household_id();
+        sqlx::query!(
+            r#"UPDATE item SET quantity = MAX(quantity + ?1, 0), version = version + 1 WHERE id = ?2 AND household_id = ?3"#,
+            delta,
+            id,
+            household
+        )
+        .execute(&mut *tx)
+        .await?;
+        let record = fetch_item(&mut *tx, id, actor)
+            .await?
+            .ok_or(StoreError::NotFound)?;
+        record_event(
+            &mut tx,
+            id,
+            EventKind::Updated,
+            Some(&previous),
+            Some(&record),
+            actor,
+        )
+        .await?;
+        restock_shortfall(&mut tx, &previous, &record, actor).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
     pub async fn new(dsn: &str) -> Self {
         let pool = SqlitePool::connect(dsn)
             .await
@@ -120,3 +1756,11 @@ impl SqliteItemStore {
     }
 }
 pub type ItemStore = Arc<dyn Store<Item> + Send + Sync>;
+pub type LocationStore = Arc<dyn Locations + Send + Sync>;
+pub type HistoryStore = Arc<dyn History + Send + Sync>;
+pub type UserStore = Arc<dyn Users + Send + Sync>;
+pub type RecipeStore = Arc<dyn Recipes + Send + Sync>;
+pub type MealPlanStore = Arc<dyn MealPlans + Send + Sync>;
+pub type PurchaseStore = Arc<dyn Purchases + Send + Sync>;
+pub type ShopStore = Arc<dyn Shops + Send + Sync>;
+pub type CartStore = Arc<dyn Carts + Send + Sync>;
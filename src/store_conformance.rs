@@ -0,0 +1,1325 @@
+//! Behaviour every store backend has to share, run against the in-memory store, sqlite and
+//! a throwaway PostgreSQL server started from the `initdb` and `pg_ctl` found on the `PATH`.
+
+use chrono::{Duration, NaiveDate, Utc};
+use std::os::unix::fs::MetadataExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::actor::Actor;
+use crate::item::{Item, Quantity, State, Unit};
+use crate::item_event::EventKind;
+use crate::location::Location;
+use crate::memory_store::MemoryItemStore;
+use crate::page::{PageRequest, SortKey};
+use crate::postgres_store::PostgresItemStore;
+use crate::purchase::{Price, Purchase};
+use crate::recipe::Ingredient;
+use crate::store::{
+    Carts, History, Locations, MealPlans, Purchases, Recipes, Shops, SqliteItemStore, Store,
+    StoreError, Users,
+};
+
+trait ConformingStore:
+    Store<Item> + Locations + History + Users + Recipes + MealPlans + Purchases + Shops + Carts + Sync
+{
+}
+
+impl<S> ConformingStore for S where
+    S: Store<Item>
+        + Locations
+        + History
+        + Users
+        + Recipes
+        + MealPlans
+        + Purchases
+        + Shops
+        + Carts
+        + Sync
+{
+}
+
+macro_rules! checks {
+    ($($check:ident),* $(,)?) => {
+        async fn run_checks(store: &impl ConformingStore) {
+            $(
+                eprintln!("checking {}", stringify!($check));
+                $check(store, &household(store, stringify!($check)).await).await;
+            )*
+        }
+    };
+}
+
+checks!(
+    items_round_trip,
+    create_many_is_atomic,
+    stale_update_conflicts,
+    missing_items_are_not_found,
+    configured_states_are_stored,
+    merge_folds_duplicates_into_one,
+    merge_keeps_the_aisles_of_folded_items,
+    history_follows_the_item,
+    quantity_adjustments_restock_par,
+    pages_follow_the_cursor,
+    locations_scope_nested_items,
+    locations_keep_to_the_household,
+    expiring_items_soonest_first,
+    search_tolerates_typos,
+    sessions_expire,
+    recipes_keep_to_the_household,
+    recipes_outlive_their_items,
+    meal_plan_shops_missing_ingredients,
+    purchases_outlive_their_item,
+    buying_saves_the_item_with_its_purchase,
+    aisles_order_the_shop,
+    shortfall_keeps_its_aisle,
+    check_out_puts_the_cart_in_stock,
+);
+
+#[tokio::test]
+async fn memory_store_conforms() {
+    run_checks(&MemoryItemStore::default()).await;
+}
+
+#[tokio::test]
+async fn sqlite_store_conforms() {
+    let dir = TempDir::new("sqlite");
+    let dsn = format!("sqlite://{}/pantry.db?mode=rwc", dir.path.display());
+    run_checks(&SqliteItemStore::new(&dsn).await).await;
+}
+
+#[tokio::test]
+async fn postgres_store_conforms() {
+    let Some(server) = PostgresServer::start() else {
+        eprintln!("skipping, no PostgreSQL server binaries on the PATH");
+        return;
+    };
+    run_checks(&PostgresItemStore::new(&server.dsn()).await).await;
+}
+
+/// Every check runs in a household of its own, the checks share one database.
+async fn household(store: &impl ConformingStore, name: &str) -> Actor {
+    store
+        .create_user(name, "not a password hash", name)
+        .await
+        .expect("user is created");
+    let user = store
+        .read_user_by_name(name)
+        .await
+        .expect("user is read")
+        .expect("user exists");
+    Actor::from(user)
+}
+
+fn stock(name: &str, quantity: f64, unit: Unit) -> Item {
+    Item::new(0, name.to_string(), quantity, unit, State::STOCK)
+}
+
+fn names(items: &[Item]) -> Vec<&str> {
+    items.iter().map(|item| item.name.as_str()).collect()
+}
+
+async fn items_round_trip(store: &impl ConformingStore, actor: &Actor) {
+    let location = store
+        .create_location(Location::new(0, "Pantry".to_string(), None), actor)
+        .await
+        .unwrap();
+    let expires_on = NaiveDate::from_ymd_opt(2030, 1, 31);
+    let id = store
+        .create(
+            stock("Flour", 1.5, Unit::Kilogram)
+                .with_location(Some(location))
+                .with_expiry(expires_on)
+                .with_par(Some(1.0)),
+            actor,
+        )
+        .await
+        .unwrap();
+
+    let item = store.read(id, actor).await.unwrap();
+    assert_eq!(item.id, id);
+    assert_eq!(item.name, "Flour");
+    assert_eq!(item.quantity, 1.5);
+    assert_eq!(item.unit, Unit::Kilogram);
+    assert_eq!(item.state, State::STOCK);
+    assert_eq!(item.location_id, Some(location));
+    assert_eq!(item.expires_on, expires_on);
+    assert_eq!(item.par_quantity, Some(1.0));
+    assert_eq!(item.version, 1);
+
+    let stranger = household(store, "items_round_trip_stranger").await;
+    assert!(matches!(
+        store.read(id, &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(
+        store
+            .read_many_from_state(State::STOCK, &stranger)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+async fn create_many_is_atomic(store: &impl ConformingStore, actor: &Actor) {
+    let ids = store
+        .create_many(
+            vec![
+                stock("Rice", 1.0, Unit::Kilogram),
+                stock("Oats", 500.0, Unit::Gram),
+            ],
+            actor,
+        )
+        .await
+        .unwrap();
+    assert_eq!(ids.len(), 2);
+
+    let result = store
+        .create_many(
+            vec![
+                stock("Pasta", 1.0, Unit::Kilogram),
+                stock("Beans", 2.0, Unit::Piece).with_location(Some(i64::MAX)),
+            ],
+            actor,
+        )
+        .await;
+    assert!(matches!(result, Err(StoreError::NotFound)));
+    let mut stored = store
+        .read_many_from_state(State::STOCK, actor)
+        .await
+        .unwrap();
+    stored.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(names(&stored), ["Oats", "Rice"]);
+}
+
+async fn stale_update_conflicts(store: &impl ConformingStore, actor: &Actor) {
+    let id = store
+        .create(stock("Milk", 1.0, Unit::Litre), actor)
+        .await
+        .unwrap();
+    let read = store.read(id, actor).await.unwrap();
+
+    store
+        .update(
+            Item {
+                quantity: 2.0,
+                ..read.clone()
+            },
+            actor,
+        )
+        .await
+        .unwrap();
+    let updated = store.read(id, actor).await.unwrap();
+    assert_eq!(updated.quantity, 2.0);
+    assert_eq!(updated.version, read.version + 1);
+
+    let stale = store
+        .update(
+            Item {
+                quantity: 3.0,
+                ..read
+            },
+            actor,
+        )
+        .await;
+    assert!(matches!(stale, Err(StoreError::Conflict)));
+    assert_eq!(store.read(id, actor).await.unwrap().quantity, 2.0);
+}
+
+async fn configured_states_are_stored(store: &impl ConformingStore, actor: &Actor) {
+    // Any state the configuration adds, like "opened"
+    let opened = State::from(7);
+    let id = store
+        .create(
+            Item::new(0, "Jam".to_string(), 1.0, Unit::Piece, opened),
+            actor,
+        )
+        .await
+        .unwrap();
+
+    let item = store.read(id, actor).await.unwrap();
+    assert_eq!(item.state, opened);
+    assert_eq!(
+        names(&store.read_many_from_state(opened, actor).await.unwrap()),
+        ["Jam"]
+    );
+
+    let item = Item {
+        state: State::STOCK,
+        ..item
+    };
+    store.update(item, actor).await.unwrap();
+    assert!(
+        store
+            .read_many_from_state(opened, actor)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+async fn missing_items_are_not_found(store: &impl ConformingStore, actor: &Actor) {
+    let id = store
+        .create(stock("Butter", 1.0, Unit::Piece), actor)
+        .await
+        .unwrap();
+    let item = store.read(id, actor).await.unwrap();
+    let stranger = household(store, "missing_items_are_not_found_stranger").await;
+
+    assert!(matches!(
+        store.delete(id, &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(matches!(
+        store.update(item.clone(), &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(matches!(
+        store.consume(id, 1.0, &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+    assert_eq!(store.read(id, actor).await.unwrap().quantity, 1.0);
+
+    store.delete(id, actor).await.unwrap();
+    assert!(matches!(
+        store.delete(id, actor).await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(matches!(
+        store.update(item, actor).await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(matches!(
+        store.restock(id, 1.0, actor).await,
+        Err(StoreError::NotFound)
+    ));
+}
+
+async fn merge_folds_duplicates_into_one(store: &impl ConformingStore, actor: &Actor) {
+    let expires_on = NaiveDate::from_ymd_opt(2030, 1, 31);
+    let into = store
+        .create(stock("Rice", 1.0, Unit::Kilogram), actor)
+        .await
+        .unwrap();
+    let from = store
+        .create(
+            stock("rice ", 500.0, Unit::Gram).with_expiry(expires_on),
+            actor,
+        )
+        .await
+        .unwrap();
+    let oil = store
+        .create(stock("Rice oil", 1.0, Unit::Litre), actor)
+        .await
+        .unwrap();
+    let recipe = store.create_recipe("Pilaf", actor).await.unwrap();
+    store
+        .create_ingredient(
+            Ingredient {
+                id: 0,
+                recipe_id: recipe,
+                item_id: from,
+                name: String::new(),
+                quantity: 200.0,
+                unit: Unit::Gram,
+            },
+            actor,
+        )
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        store.merge(into, &[oil], actor).await,
+        Err(StoreError::Validation(_))
+    ));
+    assert!(matches!(
+        store.merge(into, &[into], actor).await,
+        Err(StoreError::Validation(_))
+    ));
+    store.merge(into, &[from], actor).await.unwrap();
+
+    let merged = store.read(into, actor).await.unwrap();
+    assert_eq!(merged.name, "Rice");
+    assert_eq!(merged.quantity, 1.5);
+    assert_eq!(merged.unit, Unit::Kilogram);
+    assert_eq!(merged.expires_on, expires_on);
+    assert!(matches!(
+        store.read(from, actor).await,
+        Err(StoreError::NotFound)
+    ));
+    let ingredients = store.read_recipe(recipe, actor).await.unwrap().ingredients;
+    assert_eq!(ingredients.len(), 1);
+    assert_eq!(ingredients[0].item_id, into);
+    assert_eq!(store.read(oil, actor).await.unwrap().quantity, 1.0);
+
+    let stranger = household(store, "merge_folds_duplicates_into_one_stranger").await;
+    let foreign = store
+        .create(stock("Rice", 1.0, Unit::Kilogram), &stranger)
+        .await
+        .unwrap();
+    assert!(matches!(
+        store.merge(into, &[foreign], actor).await,
+        Err(StoreError::NotFound)
+    ));
+    assert_eq!(store.read(foreign, &stranger).await.unwrap().quantity, 1.0);
+}
+
+async fn merge_keeps_the_aisles_of_folded_items(store: &impl ConformingStore, actor: &Actor) {
+    let into = store
+        .create(stock("Flour", 1.0, Unit::Kilogram), actor)
+        .await
+        .unwrap();
+    let from = store
+        .create(stock("flour", 500.0, Unit::Gram), actor)
+        .await
+        .unwrap();
+    let market = store.create_shop("Market", actor).await.unwrap();
+    let baking = store.create_aisle(market, "Baking", actor).await.unwrap();
+    let bulk = store.create_aisle(market, "Bulk", actor).await.unwrap();
+    let corner = store.create_shop("Corner shop", actor).await.unwrap();
+    let dry_goods = store
+        .create_aisle(corner, "Dry goods", actor)
+        .await
+        .unwrap();
+    store
+        .place_item(into, market, Some(baking), actor)
+        .await
+        .unwrap();
+    store
+        .place_item(from, market, Some(bulk), actor)
+        .await
+        .unwrap();
+    store
+        .place_item(from, corner, Some(dry_goods), actor)
+        .await
+        .unwrap();
+
+    store.merge(into, &[from], actor).await.unwrap();
+
+    let in_market = store.read_placements(market, actor).await.unwrap();
+    assert_eq!(in_market.get(&into), Some(&baking));
+    assert!(!in_market.contains_key(&from));
+    let in_corner = store.read_placements(corner, actor).await.unwrap();
+    assert_eq!(in_corner.get(&into), Some(&dry_goods));
+    assert!(!in_corner.contains_key(&from));
+}
+
+async fn history_follows_the_item(store: &impl ConformingStore, actor: &Actor) {
+    let id = store
+        .create(stock("Tea", 20.0, Unit::Piece), actor)
+        .await
+        .unwrap();
+    let item = store.read(id, actor).await.unwrap();
+    store
+        .update(
+            Item {
+                quantity: 10.0,
+                ..item
+            },
+            actor,
+        )
+        .await
+        .unwrap();
+    store.delete(id, actor).await.unwrap();
+
+    assert!(matches!(
+        store.read(id, actor).await,
+        Err(StoreError::NotFound)
+    ));
+    let kinds: Vec<EventKind> = store
+        .read_history(id, actor)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|event| event.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        [EventKind::Deleted, EventKind::Updated, EventKind::Created]
+    );
+}
+
+async fn quantity_adjustments_restock_par(store: &impl ConformingStore, actor: &Actor) {
+    let id = store
+        .create(stock("Eggs", 2.0, Unit::Piece).with_par(Some(6.0)), actor)
+        .await
+        .unwrap();
+
+    store.consume(id, 5.0, actor).await.unwrap();
+    let item = store.read(id, actor).await.unwrap();
+    assert_eq!(item.quantity, 0.0);
+    assert_eq!(item.version, 2);
+    let shopping = store
+        .read_many_from_state(State::SHOPPING, actor)
+        .await
+        .unwrap();
+    assert_eq!(names(&shopping), ["Eggs"]);
+    assert_eq!(shopping[0].quantity, 6.0);
+
+    store.restock(id, 4.0, actor).await.unwrap();
+    assert_eq!(store.read(id, actor).await.unwrap().quantity, 4.0);
+}
+
+async fn pages_follow_the_cursor(store: &impl ConformingStore, actor: &Actor) {
+    for (name, quantity, unit) in [
+        ("b", 5.0, Unit::Gram),
+        ("A", 4.0, Unit::Kilogram),
+        ("c", 3.0, Unit::Gram),
+        ("d", 2.0, Unit::Litre),
+        ("e", 1.0, Unit::Piece),
+    ] {
+        store
+            .create(stock(name, quantity, unit), actor)
+            .await
+            .unwrap();
+    }
+
+    // Quantities compare in grams and millilitres, 2 l comes after 5 g
+    for (sort, expected) in [
+        (SortKey::Name, ["A", "b", "c", "d", "e"]),
+        (SortKey::Quantity, ["e", "c", "b", "d", "A"]),
+    ] {
+        let mut request = PageRequest {
+            sort,
+            location: None,
+            after: None,
+            limit: 2,
+        };
+        let mut seen = Vec::new();
+        loop {
+            let page = store
+                .read_page(State::STOCK, &request, actor)
+                .await
+                .unwrap();
+            assert!(page.records.len() <= 2);
+            seen.extend(page.records.into_iter().map(|item| item.name));
+            match page.next {
+                Some(next) => request.after = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(seen, expected, "sorted by {sort}");
+    }
+}
+
+async fn locations_scope_nested_items(store: &impl ConformingStore, actor: &Actor) {
+    let house = store
+        .create_location(Location::new(0, "House".to_string(), None), actor)
+        .await
+        .unwrap();
+    let kitchen = store
+        .create_location(Location::new(0, "Kitchen".to_string(), Some(house)), actor)
+        .await
+        .unwrap();
+    let fridge = store
+        .create_location(Location::new(0, "Fridge".to_string(), Some(kitchen)), actor)
+        .await
+        .unwrap();
+    let shed = store
+        .create_location(Location::new(0, "Shed".to_string(), None), actor)
+        .await
+        .unwrap();
+    let butter = store
+        .create(
+            stock("Butter", 250.0, Unit::Gram).with_location(Some(fridge)),
+            actor,
+        )
+        .await
+        .unwrap();
+    store
+        .create(
+            stock("Charcoal", 3.0, Unit::Kilogram).with_location(Some(shed)),
+            actor,
+        )
+        .await
+        .unwrap();
+
+    let in_house = store
+        .read_many_from_state_in_location(State::STOCK, house, actor)
+        .await
+        .unwrap();
+    assert_eq!(names(&in_house), ["Butter"]);
+
+    store.delete_location(kitchen, actor).await.unwrap();
+    let mut remaining: Vec<String> = store
+        .read_locations(actor)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|location| location.name)
+        .collect();
+    remaining.sort();
+    assert_eq!(remaining, ["House", "Shed"]);
+    assert_eq!(store.read(butter, actor).await.unwrap().location_id, None);
+}
+
+async fn locations_keep_to_the_household(store: &impl ConformingStore, actor: &Actor) {
+    let stranger = household(store, "locations_keep_to_the_household_stranger").await;
+    let foreign = store
+        .create_location(Location::new(0, "Cellar".to_string(), None), &stranger)
+        .await
+        .unwrap();
+    store
+        .create(
+            stock("Wine", 6.0, Unit::Piece).with_location(Some(foreign)),
+            &stranger,
+        )
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        store
+            .create(
+                stock("Beer", 6.0, Unit::Piece).with_location(Some(foreign)),
+                actor
+            )
+            .await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(matches!(
+        store
+            .create_location(Location::new(0, "Rack".to_string(), Some(foreign)), actor)
+            .await,
+        Err(StoreError::NotFound)
+    ));
+
+    let beer = store
+        .create(stock("Beer", 6.0, Unit::Piece), actor)
+        .await
+        .unwrap();
+    let moved = store
+        .read(beer, actor)
+        .await
+        .unwrap()
+        .with_location(Some(foreign));
+    assert!(matches!(
+        store.update(moved, actor).await,
+        Err(StoreError::NotFound)
+    ));
+    assert_eq!(store.read(beer, actor).await.unwrap().location_id, None);
+
+    assert!(
+        store
+            .read_many_from_state_in_location(State::STOCK, foreign, actor)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert!(store.read_locations(actor).await.unwrap().is_empty());
+    assert!(matches!(
+        store.delete_location(foreign, actor).await,
+        Err(StoreError::NotFound)
+    ));
+    assert_eq!(store.read_locations(&stranger).await.unwrap().len(), 1);
+}
+
+async fn expiring_items_soonest_first(store: &impl ConformingStore, actor: &Actor) {
+    let day = |day| NaiveDate::from_ymd_opt(2030, 6, day);
+    store
+        .create(
+            stock("Yoghurt", 1.0, Unit::Piece).with_expiry(day(3)),
+            actor,
+        )
+        .await
+        .unwrap();
+    store
+        .create(stock("Cream", 1.0, Unit::Piece).with_expiry(day(1)), actor)
+        .await
+        .unwrap();
+    store
+        .create(
+            stock("Cheese", 1.0, Unit::Piece).with_expiry(day(20)),
+            actor,
+        )
+        .await
+        .unwrap();
+    store
+        .create(stock("Salt", 1.0, Unit::Kilogram), actor)
+        .await
+        .unwrap();
+    store
+        .create(
+            Item::new(0, "Ham".to_string(), 1.0, Unit::Piece, State::SHOPPING).with_expiry(day(2)),
+            actor,
+        )
+        .await
+        .unwrap();
+
+    let expiring = store
+        .read_expiring_until(day(10).unwrap(), actor)
+        .await
+        .unwrap();
+    assert_eq!(names(&expiring), ["Cream", "Yoghurt"]);
+}
+
+async fn search_tolerates_typos(store: &impl ConformingStore, actor: &Actor) {
+    for name in ["Tomatoes", "Oat milk", "Butter"] {
+        store
+            .create(stock(name, 1.0, Unit::Piece), actor)
+            .await
+            .unwrap();
+    }
+
+    let found = store.search("tomatos", State::STOCK, actor).await.unwrap();
+    assert_eq!(names(&found).first(), Some(&"Tomatoes"));
+    let found = store.search("milk", State::STOCK, actor).await.unwrap();
+    assert_eq!(names(&found), ["Oat milk"]);
+    let found = store.search("milk", State::SHOPPING, actor).await.unwrap();
+    assert!(found.is_empty());
+}
+
+async fn sessions_expire(store: &impl ConformingStore, actor: &Actor) {
+    let user = store
+        .read_user_by_name(actor.name())
+        .await
+        .unwrap()
+        .unwrap();
+    let now = Utc::now().naive_utc();
+    store
+        .create_session("sessions_expire_live", user.id, now + Duration::days(1))
+        .await
+        .unwrap();
+    store
+        .create_session("sessions_expire_stale", user.id, now - Duration::minutes(1))
+        .await
+        .unwrap();
+
+    let signed_in = store
+        .read_session_user("sessions_expire_live")
+        .await
+        .unwrap();
+    assert_eq!(
+        signed_in.map(|user| user.household_id),
+        Some(actor.household_id())
+    );
+    assert!(
+        store
+            .read_session_user("sessions_expire_stale")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    store.delete_session("sessions_expire_live").await.unwrap();
+    assert!(
+        store
+            .read_session_user("sessions_expire_live")
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+async fn recipes_keep_to_the_household(store: &impl ConformingStore, actor: &Actor) {
+    let rice = store
+        .create(stock("Rice", 1.0, Unit::Kilogram), actor)
+        .await
+        .unwrap();
+    let recipe = store.create_recipe("Risotto", actor).await.unwrap();
+    let ingredient = |item_id| Ingredient {
+        id: 0,
+        recipe_id: recipe,
+        item_id,
+        name: String::new(),
+        quantity: 300.0,
+        unit: Unit::Gram,
+    };
+    let rice_ingredient = store
+        .create_ingredient(ingredient(rice), actor)
+        .await
+        .unwrap();
+    store
+        .update_recipe(recipe, "Mushroom risotto", actor)
+        .await
+        .unwrap();
+
+    let read = store.read_recipe(recipe, actor).await.unwrap();
+    assert_eq!(read.name, "Mushroom risotto");
+    assert_eq!(read.ingredients.len(), 1);
+    assert_eq!(read.ingredients[0].name, "Rice");
+    assert_eq!(read.ingredients[0].unit, Unit::Gram);
+
+    let stranger = household(store, "recipes_keep_to_the_household_stranger").await;
+    let foreign = store
+        .create(stock("Saffron", 1.0, Unit::Gram), &stranger)
+        .await
+        .unwrap();
+    assert!(
+        store
+            .create_ingredient(ingredient(foreign), actor)
+            .await
+            .is_err()
+    );
+    assert!(store.read_recipe(recipe, &stranger).await.is_err());
+    assert!(store.read_recipes(&stranger).await.unwrap().is_empty());
+    assert!(matches!(
+        store.update_recipe(recipe, "Paella", &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(matches!(
+        store.delete_ingredient(rice_ingredient, &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(matches!(
+        store.delete_recipe(recipe, &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+    let read = store.read_recipe(recipe, actor).await.unwrap();
+    assert_eq!(read.name, "Mushroom risotto");
+    assert_eq!(read.ingredients.len(), 1);
+
+    store.delete_recipe(recipe, actor).await.unwrap();
+    assert!(store.read_recipes(actor).await.unwrap().is_empty());
+    assert!(matches!(
+        store.delete_recipe(recipe, actor).await,
+        Err(StoreError::NotFound)
+    ));
+}
+
+async fn recipes_outlive_their_items(store: &impl ConformingStore, actor: &Actor) {
+    let rice = store
+        .create(stock("Rice", 1.0, Unit::Kilogram), actor)
+        .await
+        .unwrap();
+    let recipe = store.create_recipe("Pilaf", actor).await.unwrap();
+    store
+        .create_ingredient(
+            Ingredient {
+                id: 0,
+                recipe_id: recipe,
+                item_id: rice,
+                name: String::new(),
+                quantity: 300.0,
+                unit: Unit::Gram,
+            },
+            actor,
+        )
+        .await
+        .unwrap();
+    let renamed = Item {
+        name: "Basmati rice".to_string(),
+        ..store.read(rice, actor).await.unwrap()
+    };
+    store.update(renamed, actor).await.unwrap();
+    store.delete(rice, actor).await.unwrap();
+
+    let ingredients = store.read_recipe(recipe, actor).await.unwrap().ingredients;
+    assert_eq!(ingredients.len(), 1);
+    assert_eq!(ingredients[0].name, "Basmati rice");
+    assert_eq!(ingredients[0].required(), Quantity::new(300.0, Unit::Gram));
+
+    store.plan_meal(0, recipe, actor).await.unwrap();
+    let needs = store.shop_for_meal_plan(actor).await.unwrap();
+    assert_eq!(needs.len(), 1);
+    assert_eq!(needs[0].name, "Basmati rice");
+}
+
+async fn meal_plan_shops_missing_ingredients(store: &impl ConformingStore, actor: &Actor) {
+    let rice = store
+        .create(stock("Rice", 100.0, Unit::Gram), actor)
+        .await
+        .unwrap();
+    let recipe = store.create_recipe("Fried rice", actor).await.unwrap();
+    store
+        .create_ingredient(
+            Ingredient {
+                id: 0,
+                recipe_id: recipe,
+                item_id: rice,
+                name: String::new(),
+                quantity: 300.0,
+                unit: Unit::Gram,
+            },
+            actor,
+        )
+        .await
+        .unwrap();
+    store.plan_meal(4, recipe, actor).await.unwrap();
+    store.plan_meal(0, recipe, actor).await.unwrap();
+    let weekdays: Vec<i64> = store
+        .read_meal_plan(actor)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|meal| meal.weekday)
+        .collect();
+    assert_eq!(weekdays, [0, 4]);
+
+    // Shopping twice must not put the rice on the list twice
+    for _ in 0..2 {
+        let needs = store.shop_for_meal_plan(actor).await.unwrap();
+        assert_eq!(needs.len(), 1);
+        let shopping = store
+            .read_many_from_state(State::SHOPPING, actor)
+            .await
+            .unwrap();
+        assert_eq!(names(&shopping), ["Rice"]);
+        let missing = shopping[0].measure().convert(Unit::Gram).unwrap();
+        assert_eq!(missing.amount, 500.0);
+    }
+
+    let monday = store.read_meal_plan(actor).await.unwrap()[0].id;
+    let stranger = household(store, "meal_plan_shops_missing_ingredients_stranger").await;
+    assert!(matches!(
+        store.delete_planned_meal(monday, &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+    store.delete_planned_meal(monday, actor).await.unwrap();
+    assert_eq!(store.read_meal_plan(actor).await.unwrap().len(), 1);
+    assert!(matches!(
+        store.delete_planned_meal(monday, actor).await,
+        Err(StoreError::NotFound)
+    ));
+}
+
+/// Directory removed again when the test is done with it.
+struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("pantry-conformance-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("temporary directory is created");
+        Self { path }
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// PostgreSQL server listening on a socket in a temporary directory only, stopped when
+/// dropped.
+struct PostgresServer {
+    dir: TempDir,
+    owner: Option<(u32, u32)>,
+}
+
+impl PostgresServer {
+    fn start() -> Option<Self> {
+        let dir = TempDir::new("postgres");
+        // initdb refuses to run as root, containers often do, so hand it to the postgres user
+        let owner = is_root().then(|| system_user("postgres")).flatten();
+        if let Some((uid, gid)) = owner {
+            std::os::unix::fs::chown(&dir.path, Some(uid), Some(gid))
+                .expect("temporary directory is handed over");
+        }
+        let server = Self { dir, owner };
+
+        let data = server.data();
+        match server
+            .command("initdb")
+            .arg("--pgdata")
+            .arg(&data)
+            .args(["--username", "postgres", "--auth", "trust", "--no-sync"])
+            .output()
+        {
+            Ok(output) => assert!(output.status.success(), "initdb failed: {output:?}"),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+            Err(err) => panic!("initdb failed to start: {err}"),
+        }
+        let settings = format!(
+            "listen_addresses = ''\nunix_socket_directories = '{}'\nfsync = off\n",
+            server.dir.path.display()
+        );
+        let mut conf = std::fs::read_to_string(data.join("postgresql.conf")).unwrap();
+        conf.push_str(&settings);
+        std::fs::write(data.join("postgresql.conf"), conf).unwrap();
+
+        let output = server
+            .command("pg_ctl")
+            .arg("--pgdata")
+            .arg(&data)
+            .arg("--log")
+            .arg(server.dir.path.join("server.log"))
+            .args(["--wait", "start"])
+            .output()
+            .expect("pg_ctl is on the PATH");
+        assert!(output.status.success(), "pg_ctl start failed: {output:?}");
+
+        Some(server)
+    }
+
+    fn dsn(&self) -> String {
+        format!(
+            "postgres://postgres@localhost/postgres?host={}",
+            self.dir.path.display()
+        )
+    }
+
+    fn data(&self) -> PathBuf {
+        self.dir.path.join("data")
+    }
+
+    fn command(&self, program: &str) -> Command {
+        let mut command = Command::new(program);
+        if let Some((uid, gid)) = self.owner {
+            command.uid(uid).gid(gid);
+        }
+        command
+    }
+}
+
+impl Drop for PostgresServer {
+    fn drop(&mut self) {
+        let _ = self
+            .command("pg_ctl")
+            .arg("--pgdata")
+            .arg(self.data())
+            .args(["--mode", "immediate", "stop"])
+            .output();
+    }
+}
+
+fn is_root() -> bool {
+    Path::new("/proc/self")
+        .metadata()
+        .is_ok_and(|metadata| metadata.uid() == 0)
+}
+
+/// Uid and gid of a system user from `/etc/passwd`.
+fn system_user(name: &str) -> Option<(u32, u32)> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    passwd.lines().find_map(|line| {
+        let mut fields = line.split(':');
+        if fields.next()? != name {
+            return None;
+        }
+        let uid = fields.nth(1)?.parse().ok()?;
+        let gid = fields.next()?.parse().ok()?;
+        Some((uid, gid))
+    })
+}
+
+async fn purchases_outlive_their_item(store: &impl ConformingStore, actor: &Actor) {
+    let coffee = store
+        .create(stock("Coffee", 500.0, Unit::Gram), actor)
+        .await
+        .unwrap();
+    let purchase = |price, shop: Option<&str>, day| Purchase {
+        id: 0,
+        item_id: coffee,
+        name: "Coffee".to_string(),
+        quantity: 500.0,
+        unit: Unit::Gram,
+        price: Price(price),
+        shop: shop.map(str::to_string),
+        purchased_on: NaiveDate::from_ymd_opt(2025, 10, day).unwrap(),
+    };
+    let first = store
+        .record_purchase(purchase(449, Some("Corner shop"), 1), actor)
+        .await
+        .unwrap();
+    let second = store
+        .record_purchase(purchase(479, None, 15), actor)
+        .await
+        .unwrap();
+
+    let stranger = household(store, "purchases_outlive_their_item_stranger").await;
+    assert!(matches!(
+        store
+            .record_purchase(purchase(100, None, 2), &stranger)
+            .await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(store.read_purchases(&stranger).await.unwrap().is_empty());
+
+    store.delete(coffee, actor).await.unwrap();
+    let purchases = store.read_purchases(actor).await.unwrap();
+    let ids: Vec<i64> = purchases.iter().map(|purchase| purchase.id).collect();
+    assert_eq!(ids, [second, first]);
+    let first = &purchases[1];
+    assert_eq!(first.name, "Coffee");
+    assert_eq!(first.measure(), Quantity::new(500.0, Unit::Gram));
+    assert_eq!(first.price, Price(449));
+    assert_eq!(first.shop.as_deref(), Some("Corner shop"));
+    assert_eq!(
+        first.purchased_on,
+        NaiveDate::from_ymd_opt(2025, 10, 1).unwrap()
+    );
+}
+
+async fn buying_saves_the_item_with_its_purchase(store: &impl ConformingStore, actor: &Actor) {
+    let tea = store
+        .create(
+            Item::new(0, "Tea".to_string(), 1.0, Unit::Piece, State::SHOPPING),
+            actor,
+        )
+        .await
+        .unwrap();
+    let listed = store.read(tea, actor).await.unwrap();
+    let purchase = Purchase {
+        id: 0,
+        item_id: tea,
+        name: "Tea".to_string(),
+        quantity: 1.0,
+        unit: Unit::Piece,
+        price: Price(299),
+        shop: None,
+        purchased_on: NaiveDate::from_ymd_opt(2025, 10, 18).unwrap(),
+    };
+    let bought = Item {
+        state: State::STOCK,
+        ..listed
+    };
+    store
+        .buy(bought.clone(), purchase.clone(), actor)
+        .await
+        .unwrap();
+    assert_eq!(store.read(tea, actor).await.unwrap().state, State::STOCK);
+    assert_eq!(store.read_purchases(actor).await.unwrap().len(), 1);
+
+    // A stale item records no purchase either
+    assert!(matches!(
+        store.buy(bought, purchase, actor).await,
+        Err(StoreError::Conflict)
+    ));
+    assert_eq!(store.read_purchases(actor).await.unwrap().len(), 1);
+}
+
+async fn aisles_order_the_shop(store: &impl ConformingStore, actor: &Actor) {
+    let milk = store
+        .create(stock("Milk", 1.0, Unit::Litre), actor)
+        .await
+        .unwrap();
+    let bread = store
+        .create(stock("Bread", 1.0, Unit::Piece), actor)
+        .await
+        .unwrap();
+    let market = store.create_shop("Market", actor).await.unwrap();
+    let corner = store.create_shop("Corner shop", actor).await.unwrap();
+    let dairy = store.create_aisle(market, "Dairy", actor).await.unwrap();
+    let bakery = store.create_aisle(market, "Bakery", actor).await.unwrap();
+    let fridge = store.create_aisle(corner, "Fridge", actor).await.unwrap();
+
+    let shops = store.read_shops(actor).await.unwrap();
+    let shop_names: Vec<&str> = shops.iter().map(|shop| shop.name.as_str()).collect();
+    assert_eq!(shop_names, ["Corner shop", "Market"]);
+    let aisles: Vec<i64> = shops[1].aisles.iter().map(|aisle| aisle.id).collect();
+    assert_eq!(aisles, [dairy, bakery]);
+
+    store
+        .reorder_aisles(market, &[bakery, dairy], actor)
+        .await
+        .unwrap();
+    let shops = store.read_shops(actor).await.unwrap();
+    let aisles: Vec<i64> = shops[1].aisles.iter().map(|aisle| aisle.id).collect();
+    assert_eq!(aisles, [bakery, dairy]);
+    assert!(matches!(
+        store.reorder_aisles(market, &[bakery], actor).await,
+        Err(StoreError::Validation(_))
+    ));
+    assert!(matches!(
+        store.reorder_aisles(market, &[bakery, fridge], actor).await,
+        Err(StoreError::Validation(_))
+    ));
+
+    store
+        .place_item(milk, market, Some(bakery), actor)
+        .await
+        .unwrap();
+    store
+        .place_item(milk, market, Some(dairy), actor)
+        .await
+        .unwrap();
+    store
+        .place_item(bread, market, Some(bakery), actor)
+        .await
+        .unwrap();
+    store
+        .place_item(milk, corner, Some(fridge), actor)
+        .await
+        .unwrap();
+    // An aisle of another shop
+    assert!(matches!(
+        store.place_item(bread, market, Some(fridge), actor).await,
+        Err(StoreError::NotFound)
+    ));
+    let placements = store.read_placements(market, actor).await.unwrap();
+    assert_eq!(placements.len(), 2);
+    assert_eq!(placements[&milk], dairy);
+    assert_eq!(placements[&bread], bakery);
+
+    let stranger = household(store, "aisles_order_the_shop_stranger").await;
+    assert!(store.read_shops(&stranger).await.unwrap().is_empty());
+    assert!(
+        store
+            .read_placements(market, &stranger)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    let foreign = store.create_shop("Elsewhere", &stranger).await.unwrap();
+    let foreign_aisle = store
+        .create_aisle(foreign, "Fruit", &stranger)
+        .await
+        .unwrap();
+    assert!(matches!(
+        store.create_aisle(market, "Frozen", &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(matches!(
+        store
+            .place_item(milk, foreign, Some(foreign_aisle), &stranger)
+            .await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(matches!(
+        store.delete_aisle(dairy, &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+    assert!(matches!(
+        store.delete_shop(market, &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+
+    // Deleting takes the places along
+    store.place_item(bread, market, None, actor).await.unwrap();
+    store.delete_aisle(dairy, actor).await.unwrap();
+    assert!(
+        store
+            .read_placements(market, actor)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    store.delete(milk, actor).await.unwrap();
+    assert!(
+        store
+            .read_placements(corner, actor)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    store.delete_shop(market, actor).await.unwrap();
+    let shops = store.read_shops(actor).await.unwrap();
+    let shop_names: Vec<&str> = shops.iter().map(|shop| shop.name.as_str()).collect();
+    assert_eq!(shop_names, ["Corner shop"]);
+}
+
+async fn shortfall_keeps_its_aisle(store: &impl ConformingStore, actor: &Actor) {
+    let milk = store
+        .create(stock("Milk", 2.0, Unit::Litre).with_par(Some(2.0)), actor)
+        .await
+        .unwrap();
+    let market = store.create_shop("Market", actor).await.unwrap();
+    let dairy = store.create_aisle(market, "Dairy", actor).await.unwrap();
+    store
+        .place_item(milk, market, Some(dairy), actor)
+        .await
+        .unwrap();
+
+    store.consume(milk, 1.0, actor).await.unwrap();
+
+    let shopping = store
+        .read_many_from_state(State::SHOPPING, actor)
+        .await
+        .unwrap();
+    assert_eq!(names(&shopping), ["Milk"]);
+    let placements = store.read_placements(market, actor).await.unwrap();
+    assert_eq!(placements.get(&shopping[0].id), Some(&dairy));
+    assert_eq!(placements.get(&milk), Some(&dairy));
+}
+
+async fn check_out_puts_the_cart_in_stock(store: &impl ConformingStore, actor: &Actor) {
+    let flour = store
+        .create(stock("Flour", 500.0, Unit::Gram), actor)
+        .await
+        .unwrap();
+    let shopping = |name: &str, quantity, unit| {
+        Item::new(0, name.to_string(), quantity, unit, State::SHOPPING)
+    };
+    let ids = store
+        .create_many(
+            vec![
+                shopping("flour", 1.0, Unit::Kilogram),
+                shopping("Eggs", 6.0, Unit::Piece),
+                shopping("Butter", 250.0, Unit::Gram),
+            ],
+            actor,
+        )
+        .await
+        .unwrap();
+    let [more_flour, eggs, butter] = ids[..] else {
+        unreachable!()
+    };
+
+    store.set_in_cart(more_flour, true, actor).await.unwrap();
+    store.set_in_cart(eggs, true, actor).await.unwrap();
+    store.set_in_cart(butter, true, actor).await.unwrap();
+    store.set_in_cart(butter, false, actor).await.unwrap();
+    assert_eq!(store.read_cart(actor).await.unwrap(), [more_flour, eggs]);
+    let market = store.create_shop("Market", actor).await.unwrap();
+    let baking = store.create_aisle(market, "Baking", actor).await.unwrap();
+    store
+        .place_item(more_flour, market, Some(baking), actor)
+        .await
+        .unwrap();
+    // Only what is on the shopping list goes in the cart
+    assert!(matches!(
+        store.set_in_cart(flour, true, actor).await,
+        Err(StoreError::NotFound)
+    ));
+    let stranger = household(store, "check_out_puts_the_cart_in_stock_stranger").await;
+    assert!(matches!(
+        store.set_in_cart(butter, true, &stranger).await,
+        Err(StoreError::NotFound)
+    ));
+    assert_eq!(store.check_out(&stranger).await.unwrap(), 0);
+
+    assert_eq!(store.check_out(actor).await.unwrap(), 2);
+    assert!(store.read_cart(actor).await.unwrap().is_empty());
+    let stocked = store
+        .read_many_from_state(State::STOCK, actor)
+        .await
+        .unwrap();
+    let mut stocked_names = names(&stocked);
+    stocked_names.sort_unstable();
+    assert_eq!(stocked_names, ["Eggs", "Flour"]);
+    // The flour folded into the stock leaves its aisle to the one it went into
+    let placements = store.read_placements(market, actor).await.unwrap();
+    assert_eq!(placements.get(&flour), Some(&baking));
+    let flour = store.read(flour, actor).await.unwrap();
+    assert_eq!(flour.measure(), Quantity::new(1500.0, Unit::Gram));
+    assert!(matches!(
+        store.read(more_flour, actor).await,
+        Err(StoreError::NotFound)
+    ));
+    let shopping_list = store
+        .read_many_from_state(State::SHOPPING, actor)
+        .await
+        .unwrap();
+    assert_eq!(names(&shopping_list), ["Butter"]);
+
+    // Leaving the list by an edit empties the cart as well
+    store.set_in_cart(butter, true, actor).await.unwrap();
+    let butter = store.read(butter, actor).await.unwrap();
+    store
+        .update(
+            Item {
+                state: State::STOCK,
+                ..butter.clone()
+            },
+            actor,
+        )
+        .await
+        .unwrap();
+    let butter = store.read(butter.id, actor).await.unwrap();
+    store
+        .update(
+            Item {
+                state: State::SHOPPING,
+                ..butter
+            },
+            actor,
+        )
+        .await
+        .unwrap();
+    assert!(store.read_cart(actor).await.unwrap().is_empty());
+}
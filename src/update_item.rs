@@ -1,7 +1,16 @@
-use crate::item::Item;
-use crate::store::ItemStore;
+use crate::actor::Actor;
+use crate::error_fragment;
+use crate::item::{Item, State as ItemState};
+use crate::item_validation::{self, ItemFormErrors};
+use crate::list_locations::LocationOptionTemplate;
+use crate::live_updates::LiveUpdates;
+use crate::location::Location;
+use crate::purchase::Purchase;
+use crate::states::StateDefinition;
+use crate::store::{ItemStore, LocationStore, PurchaseStore, StoreError};
 use askama::Template;
 use axum::Form;
+use axum::extract::rejection::FormRejection;
 use axum::response::{Html, Response};
 use axum::{
     debug_handler,
@@ -16,44 +25,138 @@ pub struct UpdateItemForm {
     id: i64,
     name: String,
     quantity: f64,
+    unit: crate::item::Unit,
     state: crate::item::State,
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    location: Option<i64>,
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    expires_on: Option<chrono::NaiveDate>,
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    par_quantity: Option<f64>,
+    /// Paid for a shopping item moving to the stock, see [`Purchase`]
+    #[serde(default)]
+    price: Option<String>,
+    #[serde(default, deserialize_with = "crate::form::empty_as_none")]
+    shop: Option<String>,
+    /// Version of the item the form was rendered from
+    version: i64,
 }
 
-#[debug_handler]
+#[debug_handler(state = crate::AppState)]
 pub async fn update_item(
     State(pool): State<ItemStore>,
-    Form(form): Form<UpdateItemForm>,
+    State(locations): State<LocationStore>,
+    State(purchases): State<PurchaseStore>,
+    State(live): State<LiveUpdates>,
+    actor: Actor,
+    form: Result<Form<UpdateItemForm>, FormRejection>,
 ) -> impl IntoResponse {
-    match pool
-        .update(Item::new(form.id, form.name, form.quantity, form.state))
-        .await
-    {
-        Ok(_) => StatusCode::OK.into_response(),
+    let form = match form {
+        Ok(Form(form)) => form,
+        Err(rejection) => return ItemFormErrors::from_rejection(rejection).into_response(),
+    };
+    let saved = match pool.read(form.id, &actor).await {
+        Ok(saved) => saved,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to read item");
+            return error_fragment::store_error(&err, "Failed to update item");
+        }
+    };
+    let mine = Item::new(form.id, form.name, form.quantity, form.unit, form.state)
+        .with_location(form.location)
+        .with_expiry(form.expires_on)
+        .with_par(form.par_quantity)
+        .with_version(form.version);
+    // The move is checked from the state read here, an update based on any other version
+    // of the item conflicts
+    let mine = match item_validation::validate(mine, Some(saved.state)) {
+        Ok(mine) => mine,
+        Err(errors) => return errors.into_response(),
+    };
+    let bought = saved.state == ItemState::SHOPPING && mine.state == ItemState::STOCK;
+    let price = match item_validation::price(form.price.as_deref()) {
+        Ok(price) => price.filter(|_| bought),
+        Err(errors) => return errors.into_response(),
+    };
+
+    // The price is recorded along with the move to the stock, or neither happens
+    let saved_item = match price {
+        Some(price) => {
+            let purchase = Purchase {
+                id: 0,
+                item_id: mine.id,
+                name: mine.name.clone(),
+                quantity: mine.quantity,
+                unit: mine.unit,
+                price,
+                shop: form.shop,
+                purchased_on: chrono::Local::now().date_naive(),
+            };
+            purchases
+                .buy(mine.clone(), purchase, &actor)
+                .await
+                .map(|_| ())
+        }
+        None => pool.update(mine.clone(), &actor).await,
+    };
+
+    match saved_item {
+        Ok(_) => {
+            // The item may have moved lists or topped up the shopping list
+            live.publish(&actor, ItemState::all());
+            StatusCode::OK.into_response()
+        }
+        Err(StoreError::Conflict) => {
+            let saved = match pool.read(mine.id, &actor).await {
+                Ok(saved) => saved,
+                Err(err) => {
+                    tracing::error!(err = %err, "failed to read conflicting item");
+                    return error_fragment::store_error(&err, "Failed to read the saved item");
+                }
+            };
+            match locations.read_locations(&actor).await {
+                Ok(locations) => (
+                    StatusCode::CONFLICT,
+                    HtmlTemplate(UpdateItemConflictTemplate::new(&mine, &saved, &locations)),
+                )
+                    .into_response(),
+                Err(err) => {
+                    tracing::error!(err = %err, "failed to read locations");
+                    error_fragment::store_error(&err, "Failed to get locations")
+                }
+            }
+        }
         Err(err) => {
             tracing::error!(err = %err, "failed to update item");
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to update item").into_response()
+            error_fragment::store_error(&err, "Failed to update item")
         }
     }
 }
 
-#[debug_handler]
+#[debug_handler(state = crate::AppState)]
 pub async fn get_update_item(
     State(pool): State<ItemStore>,
+    State(locations): State<LocationStore>,
+    actor: Actor,
     Path(id): Path<i64>,
 ) -> impl IntoResponse {
-    match pool.read(id).await {
-        Ok(item) => {
-            let template =
-                UpdateItemFormTemplate::new(item.id, item.name, item.quantity, item.state);
+    let item = match pool.read(id, &actor).await {
+        Ok(item) => item,
+        Err(err) => {
+            tracing::error!(err = %err, "failed to get requested item");
+            return error_fragment::store_error(&err, "Failed to get requested item");
+        }
+    };
+
+    match locations.read_locations(&actor).await {
+        Ok(locations) => {
+            let locations = LocationOptionTemplate::from_locations(&locations, item.location_id);
+            let template = UpdateItemFormTemplate::new(item, locations);
             HtmlTemplate(template).into_response()
         }
         Err(err) => {
-            tracing::error!(err = %err, "failed to get requested item");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "Failed to get requested item",
-            )
-                .into_response()
+            tracing::error!(err = %err, "failed to read locations");
+            error_fragment::store_error(&err, "Failed to get locations")
         }
     }
 }
@@ -64,16 +167,103 @@ struct UpdateItemFormTemplate {
     id: i64,
     name: String,
     quantity: f64,
+    unit: crate::item::Unit,
+    units: &'static [crate::item::Unit],
     original_state: crate::item::State,
+    /// The state the item is in and the ones it can be moved to
+    states: Vec<&'static StateDefinition>,
+    /// Ask for the price paid, the item is on the shopping list
+    purchasable: bool,
+    expires_on: Option<chrono::NaiveDate>,
+    par_quantity: Option<f64>,
+    version: i64,
+    name_max_chars: usize,
+    locations: Vec<LocationOptionTemplate>,
 }
 
 impl UpdateItemFormTemplate {
-    fn new(id: i64, name: String, quantity: f64, original_state: crate::item::State) -> Self {
+    fn new(item: Item, locations: Vec<LocationOptionTemplate>) -> Self {
+        Self {
+            id: item.id,
+            name: item.name,
+            quantity: item.quantity,
+            unit: item.unit,
+            units: crate::item::Unit::ALL,
+            original_state: item.state,
+            states: std::iter::once(&item.state)
+                .chain(item.state.transitions())
+                .filter_map(|state| state.definition())
+                .collect(),
+            purchasable: item.state == ItemState::SHOPPING,
+            expires_on: item.expires_on,
+            par_quantity: item.par_quantity,
+            version: item.version,
+            name_max_chars: item_validation::NAME_MAX_CHARS,
+            locations,
+        }
+    }
+}
+
+/// Shown in the edit modal when someone else saved the item after the form was opened.
+#[derive(Template)]
+#[template(path = "update_item_conflict.html")]
+struct UpdateItemConflictTemplate {
+    id: i64,
+    saved_version: i64,
+    fields: Vec<ConflictField>,
+}
+
+struct ConflictField {
+    label: &'static str,
+    mine: String,
+    saved: String,
+}
+
+impl ConflictField {
+    fn differs(&self) -> bool {
+        self.mine != self.saved
+    }
+}
+
+impl UpdateItemConflictTemplate {
+    fn new(mine: &Item, saved: &Item, locations: &[Location]) -> Self {
+        let paths = crate::location::paths(locations);
+        let describe = |item: &Item| {
+            [
+                ("Name", item.name.clone()),
+                ("Quantity", format!("{} {}", item.quantity, item.unit)),
+                ("State", item.state.to_string()),
+                (
+                    "Minimum Stock",
+                    item.par_quantity
+                        .map(|par| format!("{par} {}", item.unit))
+                        .unwrap_or_default(),
+                ),
+                (
+                    "Best Before",
+                    item.expires_on
+                        .map(|date| date.to_string())
+                        .unwrap_or_default(),
+                ),
+                (
+                    "Location",
+                    item.location_id
+                        .and_then(|id| paths.get(&id).cloned())
+                        .unwrap_or_default(),
+                ),
+            ]
+        };
+
+        let fields = describe(mine)
+            .into_iter()
+            .zip(describe(saved))
+            .map(|((label, mine), (_, saved))| ConflictField { label, mine, saved })
+            .collect();
+
         Self {
-            id,
-            name,
-            quantity,
-            original_state,
+            id: saved.id,
+            saved_version: saved.version,
+            fields,
         }
     }
 }
@@ -0,0 +1,29 @@
+use crate::{actor::Actor, error_fragment, store::RecipeStore};
+use axum::{
+    Form, debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct UpdateRecipeForm {
+    name: String,
+}
+
+#[debug_handler]
+pub async fn update_recipe(
+    State(pool): State<RecipeStore>,
+    actor: Actor,
+    Path(id): Path<i64>,
+    Form(form): Form<UpdateRecipeForm>,
+) -> impl IntoResponse {
+    match pool.update_recipe(id, &form.name, &actor).await {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(err) => {
+            tracing::error!(err = %err, "failed to update recipe");
+            error_fragment::store_error(&err, "Failed to update recipe")
+        }
+    }
+}
@@ -0,0 +1,7 @@
+#[derive(Clone)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    pub password_hash: String,
+    pub household_id: i64,
+}